@@ -13,7 +13,10 @@ use thaw::{ConfigProvider, Theme, ToasterProvider};
 use crate::{
     app::WalletPublicKeyContext,
     components::{AdminRoute, Footer, Nav},
-    pages::{AdminDashboard, BuyGamePage, HomePage, HowItWorksPage, PlayPage, PublishGamePage},
+    pages::{
+        AdminDashboard, BuyGamePage, HomePage, HowItWorksPage, PlayPage, PublishGamePage,
+        RomSandboxPage,
+    },
     utils::LS_PUBLIC_KEY,
 };
 
@@ -50,6 +53,7 @@ pub fn App() -> impl IntoView {
                             <Route path=StaticSegment("/how-it-works") view=HowItWorksPage />
                             <Route path=StaticSegment("/play") view=PlayPage />
                             <Route path=StaticSegment("/publish") view=PublishGamePage />
+                            <Route path=StaticSegment("/dev") view=RomSandboxPage />
                             <ParentRoute path=StaticSegment("/admin") view=AdminRoute>
                                 <Route path=StaticSegment("") view=AdminDashboard />
                             </ParentRoute>
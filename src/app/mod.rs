@@ -18,8 +18,9 @@ pub struct App {
 impl App {
     pub async fn new() -> AppResult<App> {
         use crate::{
+            auth::BackendAuth,
             config::{load_config, DEFAULT_CONFIG_FILE},
-            server::ApiClient,
+            server::{ApiClient, SessionStore},
         };
         use axum::Router;
 
@@ -41,7 +42,20 @@ impl App {
         let leptos_options = leptos_config.leptos_options;
         let routes = generate_route_list(App);
         let solana_client = Arc::new(RpcClient::new(app_config.solana.rpc_url.clone()));
-        let api_client = ApiClient::new(Client::new(), app_config.app.backend_url.clone());
+        let backend_keypair = match (app_config.app.backend_auth, app_config.app.backend_keypair) {
+            (BackendAuth::Server, Some(keypair)) => Some(Keypair::new_from_array(
+                keypair[..32]
+                    .try_into()
+                    .map_err(|_| AppError::custom("Invalid keypair bytes"))?,
+            )),
+            _ => None,
+        };
+        let api_client = ApiClient::new(
+            Client::new(),
+            app_config.app.backend_url.clone(),
+            backend_keypair,
+        );
+        let sessions = Arc::new(SessionStore::default());
 
         let bundlr_keypair = Keypair::new_from_array(
             app_config.solana.bundlr_keypair[..32]
@@ -79,6 +93,7 @@ impl App {
                         provide_context(solana_client.clone());
                         provide_context(bundlr.clone());
                         provide_context(api_client.clone());
+                        provide_context(sessions.clone());
                     }
                 },
                 {
@@ -100,9 +115,13 @@ impl App {
             .await
             .map_err(|e| AppError::custom(e.to_string()))?;
 
-        axum::serve(listener, self.router.into_make_service())
-            .await
-            .map_err(|e| AppError::custom(e.to_string()))?;
+        axum::serve(
+            listener,
+            self.router
+                .into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .map_err(|e| AppError::custom(e.to_string()))?;
         Ok(())
     }
 }
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// How long a [`GameGrant`] is good for
+pub const GRANT_SECS: i64 = 2 * 60 * 60;
+
+/// How the app server proves the signed-in wallet to the backend. See "Backend API" in the
+/// README.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendAuth {
+    /// The wallet signs a [`GameGrant`] for each request and the server forwards it, which
+    /// is what backends without server keys check
+    #[default]
+    Wallet,
+    /// The server signs each request with `app.backend_keypair`
+    Server,
+}
+
+/// The wallet's signature over [`GameGrant::message`], for one game until `valid_period`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameGrant {
+    pub signature: String,
+    /// Unix seconds
+    pub valid_period: i64,
+}
+
+impl GameGrant {
+    pub fn message(game_name: &str, valid_period: i64) -> String {
+        format!("Replayer: game '{game_name}' (valid until: {valid_period})")
+    }
+}
@@ -0,0 +1,5 @@
+mod game_grant;
+mod siws;
+
+pub use game_grant::*;
+pub use siws::*;
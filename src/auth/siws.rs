@@ -0,0 +1,158 @@
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+
+/// How long a sign-in lasts, and with it the session cookie
+pub const SESSION_SECS: i64 = 24 * 60 * 60;
+
+/// What a sign-in lets the wallet do, so a signature for one can't be used for the other.
+/// The discriminants are signed into backend requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Purpose {
+    Play = 0,
+    Publish = 1,
+}
+
+impl Purpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Purpose::Play => "play",
+            Purpose::Publish => "publish",
+        }
+    }
+
+    fn statement(&self) -> &'static str {
+        match self {
+            Purpose::Play => "Sign in to Replayer to play the games you own.",
+            Purpose::Publish => "Sign in to Replayer to publish games.",
+        }
+    }
+}
+
+/// Sign In With Solana message; the server issues it and the wallet signs it once per
+/// session and purpose. The nonce is only good for one sign-in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignInMessage {
+    pub domain: String,
+    pub address: String,
+    pub statement: String,
+    pub purpose: Purpose,
+    /// Solana cluster the deployment runs on, e.g. `devnet`
+    pub cluster: String,
+    pub nonce: String,
+    /// Unix seconds
+    pub issued_at: i64,
+    /// Unix seconds
+    pub expiration_time: i64,
+}
+
+impl SignInMessage {
+    pub fn new(
+        domain: String,
+        address: String,
+        purpose: Purpose,
+        cluster: String,
+        nonce: String,
+        now: i64,
+    ) -> SignInMessage {
+        SignInMessage {
+            domain,
+            address,
+            statement: purpose.statement().to_string(),
+            purpose,
+            cluster,
+            nonce,
+            issued_at: now,
+            expiration_time: now + SESSION_SECS,
+        }
+    }
+
+    /// The text the wallet signs, laid out as the SIWS spec has it
+    pub fn to_text(&self) -> String {
+        format!(
+            "{} wants you to sign in with your Solana account:\n{}\n\n{}\n\nPurpose: {}\nChain ID: {}\nNonce: {}\nIssued At: {}\nExpiration Time: {}",
+            self.domain,
+            self.address,
+            self.statement,
+            self.purpose.as_str(),
+            self.cluster,
+            self.nonce,
+            rfc3339(self.issued_at),
+            rfc3339(self.expiration_time),
+        )
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expiration_time
+    }
+
+    /// Checked in the browser before the wallet is asked to sign, so a server can't get a
+    /// signature for another site, wallet or purpose
+    pub fn check_issued(&self, domain: &str, address: &str, purpose: Purpose) -> AppResult<()> {
+        if self.domain != domain {
+            return Err(AppError::custom(format!(
+                "Sign-in message is for {}, not {domain}",
+                self.domain
+            )));
+        }
+        if self.address != address || self.purpose != purpose {
+            return Err(AppError::custom(
+                "Sign-in message is for another wallet or purpose",
+            ));
+        }
+        if self.statement != purpose.statement() {
+            return Err(AppError::custom("Unexpected sign-in statement"));
+        }
+        Ok(())
+    }
+
+    /// Checks this is the message the server `issued`, still valid at `now`, and that
+    /// `signature` (base58) is the address's ed25519 signature over [`Self::to_text`]
+    #[cfg(feature = "ssr")]
+    pub fn verify(&self, issued: &SignInMessage, signature: &str, now: i64) -> AppResult<()> {
+        use solana_keypair::Signature;
+        use solana_pubkey::Pubkey;
+
+        if self != issued {
+            return Err(AppError::custom("Sign-in message was altered"));
+        }
+        if self.is_expired(now) {
+            return Err(AppError::custom("Sign-in message expired"));
+        }
+
+        let address: Pubkey = self
+            .address
+            .parse()
+            .map_err(|_| AppError::custom("Invalid wallet address"))?;
+        let sig_bytes: [u8; 64] = bs58::decode(signature)
+            .into_vec()
+            .map_err(|e| AppError::custom(format!("Invalid signature encoding: {e}")))?
+            .try_into()
+            .map_err(|_| AppError::custom("Invalid signature length"))?;
+
+        if !Signature::from(sig_bytes).verify(address.as_ref(), self.to_text().as_bytes()) {
+            return Err(AppError::custom("Invalid sign-in signature"));
+        }
+        Ok(())
+    }
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ` for a unix timestamp
+pub fn rfc3339(secs: i64) -> String {
+    let (days, time) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // days since 1970-01-01 to a civil date, after Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
@@ -0,0 +1,40 @@
+//! Assembles Octo source into a CHIP-8 ROM and prints its SHA-256.
+//!
+//! Usage: replayer-asm <source.8o> <out.ch8>
+//!
+//! The same source always produces the same bytes, so the printed hash can be checked
+//! against the ROM a team publishes.
+
+use replayer_fe::vm::{assemble, rom_hash};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [source_path, out_path] = args.as_slice() else {
+        eprintln!("usage: replayer-asm <source.8o> <out.ch8>");
+        return ExitCode::FAILURE;
+    };
+
+    let source = match std::fs::read_to_string(source_path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("can't read {source_path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let rom = match assemble(&source) {
+        Ok(rom) => rom,
+        Err(err) => {
+            eprintln!("{source_path}:{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = std::fs::write(out_path, &rom) {
+        eprintln!("can't write {out_path}: {err}");
+        return ExitCode::FAILURE;
+    }
+
+    let hash: String = rom_hash(&rom).iter().map(|b| format!("{b:02x}")).collect();
+    println!("{hash}  {out_path} ({} bytes)", rom.len());
+    ExitCode::SUCCESS
+}
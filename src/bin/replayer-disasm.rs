@@ -0,0 +1,36 @@
+//! Prints an Octo-style listing of a CHIP-8 ROM.
+//!
+//! Usage: replayer-disasm <rom> [chip8|super_chip|xo_chip]
+
+use replayer_fe::vm::{disassemble, Platform};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(path) = args.first() else {
+        eprintln!("usage: replayer-disasm <rom> [chip8|super_chip|xo_chip]");
+        return ExitCode::FAILURE;
+    };
+    let platform = match args
+        .get(1)
+        .map(|id| id.parse())
+        .unwrap_or(Ok(Platform::Chip8))
+    {
+        Ok(platform) => platform,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match std::fs::read(path) {
+        Ok(rom) => {
+            print!("{}", disassemble(&rom, platform));
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("can't read {path}: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Runs a ROM without a browser, for regression tests before publishing.
+//!
+//! Usage: replayer-run <rom> [options]
+//!
+//!   --frames <n>       60Hz frames to run (default 600, or the length of a replay)
+//!   --input <file>     a `.c8replay` recording, or a script of `<frame> <keys>` lines where
+//!                      keys are the hex keys held from that frame on, `-` for none
+//!   --profile <file>   a machine profile as JSON, the `machine` of a game's metadata
+//!                      document, for its platform, quirks and speed
+//!   --platform <id>    chip8, super_chip or xo_chip (default chip8), with its default quirks
+//!                      unless a profile was given
+//!   --ticks <n>        instructions per frame (default 20)
+//!   --seed <n>         random number seed (default 0)
+//!   --png <file>       write the final framebuffer as a greyscale PNG
+//!   --ascii            print the final framebuffer
+//!
+//! Options later on the command line override earlier ones. A replay's speed overrides them
+//! all, clamped like the browser clamps it. The last line printed is the SHA-256 of the final save state, which only matches between
+//! runs that behaved identically.
+
+use replayer_fe::vm::{
+    clamp_ticks_per_frame, rom_hash, Emulator, MachineProfile, Platform, Quirks, Replay,
+    DEFAULT_TICKS_PER_FRAME, REPLAY_FILE_EXTENSION,
+};
+use sha2::{Digest, Sha256};
+use std::{fs::File, io::BufWriter, path::Path, process::ExitCode};
+
+const DEFAULT_FRAMES: usize = 600;
+// background, plane 1, plane 2, both planes
+const GREYS: [u8; 4] = [0x00, 0xFF, 0xAA, 0x55];
+const ASCII: [char; 4] = ['.', '#', '+', '@'];
+
+struct Options {
+    rom: String,
+    frames: Option<usize>,
+    input: Option<String>,
+    platform: Platform,
+    /// `None` for the platform's defaults
+    quirks: Option<Quirks>,
+    ticks_per_frame: u32,
+    seed: u32,
+    png: Option<String>,
+    ascii: bool,
+}
+
+fn main() -> ExitCode {
+    match parse_args().and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options {
+        rom: String::new(),
+        frames: None,
+        input: None,
+        platform: Platform::Chip8,
+        quirks: None,
+        ticks_per_frame: DEFAULT_TICKS_PER_FRAME,
+        seed: 0,
+        png: None,
+        ascii: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--frames" => options.frames = Some(parse_number(&value()?)?),
+            "--input" => options.input = Some(value()?),
+            "--profile" => {
+                let profile = read_profile(&value()?)?;
+                options.platform = profile.platform;
+                options.quirks = Some(profile.quirks);
+                options.ticks_per_frame = profile.ticks_per_frame;
+            }
+            "--platform" => options.platform = value()?.parse()?,
+            "--ticks" => options.ticks_per_frame = parse_number(&value()?)?,
+            "--seed" => options.seed = parse_number(&value()?)?,
+            "--png" => options.png = Some(value()?),
+            "--ascii" => options.ascii = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ => options.rom = arg,
+        }
+    }
+    if options.rom.is_empty() {
+        return Err(
+            "usage: replayer-run <rom> [--frames n] [--input file] [--profile file] \
+                    [--platform id] [--ticks n] [--seed n] [--png file] [--ascii]"
+                .into(),
+        );
+    }
+    Ok(options)
+}
+
+fn read_profile(path: &str) -> Result<MachineProfile, String> {
+    let bytes = std::fs::read(path).map_err(|err| format!("can't read {path}: {err}"))?;
+    let profile: MachineProfile =
+        serde_json::from_slice(&bytes).map_err(|err| format!("{path}: {err}"))?;
+    profile.validate().map_err(|err| format!("{path}: {err}"))?;
+    Ok(profile)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{value} isn't a number"))
+}
+
+fn run(mut options: Options) -> Result<(), String> {
+    let rom =
+        std::fs::read(&options.rom).map_err(|err| format!("can't read {}: {err}", options.rom))?;
+
+    // key mask for every frame that has been scripted
+    let mut masks = vec![];
+    let mut hold_last = false;
+    if let Some(path) = &options.input {
+        let bytes = std::fs::read(path).map_err(|err| format!("can't read {path}: {err}"))?;
+        if Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext == REPLAY_FILE_EXTENSION)
+        {
+            let replay = Replay::from_bytes(&bytes).map_err(|err| err.to_string())?;
+            if replay.rom_hash != rom_hash(&rom) {
+                return Err(format!("{path} was recorded with a different ROM"));
+            }
+            options.seed = replay.seed;
+            options.ticks_per_frame = clamp_ticks_per_frame(replay.ticks_per_frame);
+            options.frames = options.frames.or(Some(replay.frames.len()));
+            masks = replay.frames;
+        } else {
+            let script = String::from_utf8(bytes).map_err(|_| format!("{path} isn't text"))?;
+            masks = parse_script(&script).map_err(|err| format!("{path}:{err}"))?;
+            hold_last = true;
+        }
+    }
+
+    let quirks = options
+        .quirks
+        .unwrap_or_else(|| options.platform.default_quirks());
+    let ticks_per_frame = clamp_ticks_per_frame(options.ticks_per_frame);
+    let mut chip8 = Emulator::with_platform(options.platform, quirks);
+    chip8.seed_rng(options.seed);
+    chip8.load(&rom).map_err(|err| err.to_string())?;
+
+    let frames = options.frames.unwrap_or(DEFAULT_FRAMES);
+    for frame in 0..frames {
+        // scripts hold their last keys, replays release them when they end
+        let mask = match masks.get(frame) {
+            Some(&mask) => mask,
+            None if hold_last => masks.last().copied().unwrap_or(0),
+            None => 0,
+        };
+        chip8.set_key_mask(mask);
+        for _ in 0..ticks_per_frame {
+            chip8
+                .tick()
+                .map_err(|err| format!("frame {frame}: {err}"))?;
+        }
+        chip8.tick_timers();
+        if chip8.has_exited() {
+            break;
+        }
+    }
+
+    if options.ascii {
+        print!("{}", framebuffer_ascii(&chip8));
+    }
+    if let Some(path) = &options.png {
+        write_png(&chip8, path).map_err(|err| format!("can't write {path}: {err}"))?;
+    }
+    let hash: String = Sha256::digest(chip8.snapshot().to_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    println!("{hash}");
+    Ok(())
+}
+
+// expands `<frame> <keys>` lines into one key mask per frame, up to the last scripted frame
+fn parse_script(script: &str) -> Result<Vec<u16>, String> {
+    let mut masks: Vec<u16> = vec![];
+    for (i, line) in script.lines().enumerate() {
+        let code = line.split('#').next().unwrap_or_default();
+        let mut words = code.split_whitespace();
+        let Some(frame) = words.next() else {
+            continue;
+        };
+        let error = |message: String| format!("{}: {message}", i + 1);
+        let frame: usize = parse_number(frame).map_err(error)?;
+        if frame < masks.len() {
+            return Err(error(format!("frame {frame} is out of order")));
+        }
+
+        let mut mask = 0;
+        for key in words.filter(|key| *key != "-") {
+            let key = u8::from_str_radix(key, 16)
+                .ok()
+                .filter(|key| *key < 16)
+                .ok_or_else(|| error(format!("{key} isn't a hex key")))?;
+            mask |= 1 << key;
+        }
+
+        let held = masks.last().copied().unwrap_or(0);
+        masks.resize(frame, held);
+        masks.push(mask);
+    }
+    Ok(masks)
+}
+
+fn framebuffer_ascii(chip8: &Emulator) -> String {
+    chip8
+        .get_display()
+        .chunks(chip8.screen_width())
+        .flat_map(|row| {
+            row.iter()
+                .map(|pixel| ASCII[*pixel as usize & 3])
+                .chain(['\n'])
+        })
+        .collect()
+}
+
+fn write_png(chip8: &Emulator, path: &str) -> Result<(), png::EncodingError> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(
+        file,
+        chip8.screen_width() as u32,
+        chip8.screen_height() as u32,
+    );
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let pixels: Vec<u8> = chip8
+        .get_display()
+        .iter()
+        .map(|pixel| GREYS[*pixel as usize & 3])
+        .collect();
+    encoder.write_header()?.write_image_data(&pixels)
+}
@@ -0,0 +1,132 @@
+use crate::vm::{DebugCommand, DebugSnapshot};
+use leptos::prelude::*;
+use thaw::{Button, Input};
+
+const MEMORY_ROW: usize = 16;
+
+#[component]
+pub fn DebuggerPanel(
+    snapshot: RwSignal<Option<DebugSnapshot>>,
+    command: RwSignal<Option<DebugCommand>>,
+    memory_address: RwSignal<String>,
+) -> impl IntoView {
+    let stopped = move || snapshot.with(|s| s.as_ref().and_then(|s| s.stopped));
+    let send = move |cmd: DebugCommand| command.set(Some(cmd));
+
+    view! {
+        <div class="debugger">
+            <div class="debugger-controls">
+                <Button
+                    on_click=move |_| send(DebugCommand::Pause)
+                    disabled=Signal::derive(move || stopped().is_some())
+                >
+                    "Pause"
+                </Button>
+                <Button on_click=move |_| send(DebugCommand::Step)>"Step"</Button>
+                <Button on_click=move |_| send(DebugCommand::StepOver)>"Step over"</Button>
+                <Button
+                    on_click=move |_| send(DebugCommand::Continue)
+                    disabled=Signal::derive(move || stopped().is_none())
+                >
+                    "Continue"
+                </Button>
+            </div>
+            <p class="debugger-status">
+                {move || stopped().map(|reason| reason.to_string()).unwrap_or("Running".into())}
+            </p>
+
+            {move || {
+                snapshot
+                    .get()
+                    .map(|s| {
+                        let pc = s.pc;
+                        view! {
+                            <div class="debugger-registers">
+                                {s
+                                    .v_registers
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(i, value)| {
+                                        view! { <span>{format!("V{i:X} {value:02X}")}</span> }
+                                    })
+                                    .collect_view()}
+                                <span>{format!("I {:03X}", s.i_register)}</span>
+                                <span>{format!("PC {:03X}", s.pc)}</span>
+                                <span>{format!("DT {:02X}", s.delay_timer)}</span>
+                                <span>{format!("ST {:02X}", s.sound_timer)}</span>
+                            </div>
+                            <p class="debugger-stack">
+                                "Stack: "
+                                {s
+                                    .stack
+                                    .iter()
+                                    .map(|address| format!("{address:03X}"))
+                                    .collect::<Vec<_>>()
+                                    .join(" ")}
+                            </p>
+
+                            <ol class="debugger-disassembly">
+                                {s
+                                    .disassembly
+                                    .iter()
+                                    .map(|instruction| {
+                                        let address = instruction.address;
+                                        let breakpoint = s.breakpoints.contains(&address);
+                                        view! {
+                                            <li
+                                                class:current={address == pc}
+                                                class:breakpoint=breakpoint
+                                                title="Toggle breakpoint"
+                                                on:click=move |_| {
+                                                    send(DebugCommand::ToggleBreakpoint(address))
+                                                }
+                                            >
+                                                <span class="address">{format!("{address:03X}")}</span>
+                                                <span>{instruction.text.clone()}</span>
+                                            </li>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </ol>
+
+                            <table class="debugger-memory">
+                                {s
+                                    .memory
+                                    .chunks(MEMORY_ROW)
+                                    .enumerate()
+                                    .map(|(row, bytes)| {
+                                        let row_address = s.memory_base + row * MEMORY_ROW;
+                                        view! {
+                                            <tr>
+                                                <th>{format!("{row_address:04X}")}</th>
+                                                {bytes
+                                                    .iter()
+                                                    .enumerate()
+                                                    .map(|(col, byte)| {
+                                                        let address = row_address + col;
+                                                        view! {
+                                                            <td
+                                                                class:watched={s.watchpoints.contains(&address)}
+                                                                class:index={address == s.i_register as usize}
+                                                                title="Toggle write breakpoint"
+                                                                on:click=move |_| {
+                                                                    send(DebugCommand::ToggleWatchpoint(address))
+                                                                }
+                                                            >
+                                                                {format!("{byte:02X}")}
+                                                            </td>
+                                                        }
+                                                    })
+                                                    .collect_view()}
+                                            </tr>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </table>
+                        }
+                    })
+            }}
+            <Input value=memory_address placeholder="Memory address (hex)" />
+        </div>
+    }
+}
@@ -0,0 +1,117 @@
+use crate::vm::MachineProfile;
+use leptos::{html, prelude::*};
+
+/// Muted attract-mode loop of a game's demo, running while `playing` is set.
+#[component]
+pub fn DemoPreview(
+    /// Developer and name of the game whose unencrypted [`crate::vm::Demo`] is shown
+    developer: String,
+    game_name: String,
+    profile: MachineProfile,
+    #[prop(into)] playing: Signal<bool>,
+) -> impl IntoView {
+    let canvas_ref = NodeRef::<html::Canvas>::new();
+
+    #[cfg(feature = "hydrate")]
+    {
+        use crate::server::get_game_demo;
+        use crate::vm::{clamp_ticks_per_frame, Demo, EmuWasm, FRAME_RATE};
+        use leptos::task::spawn_local;
+        use std::cell::{Cell, RefCell};
+        use std::rc::Rc;
+        use std::time::Duration;
+
+        // fetched on the first hover and kept for the next
+        let demo: Rc<RefCell<Option<Demo>>> = Rc::default();
+        let fetching = Rc::new(Cell::new(false));
+        let interval = StoredValue::new(None::<IntervalHandle>);
+
+        // a fresh machine each hover, so the reel always starts from the top
+        let start = Rc::new(move |demo: &Demo| {
+            let Some(mut emu) = EmuWasm::silent(&profile, &canvas_ref) else {
+                return;
+            };
+            let loaded = emu
+                .load_game(js_sys::Uint8Array::from(demo.rom.as_slice()))
+                .and_then(|()| emu.play_replay(demo.replay.clone()));
+            if let Err(err) = loaded {
+                leptos::logging::log!("Demo error: {err}");
+                return;
+            }
+            let replay = demo.replay.clone();
+            let emu = RefCell::new(emu);
+            let handle = set_interval_with_handle(
+                move || {
+                    let mut emu = emu.borrow_mut();
+                    if !emu.is_replaying() && emu.play_replay(replay.clone()).is_err() {
+                        return;
+                    }
+                    let _ = emu.run_frame();
+                    emu.draw_screen();
+                },
+                Duration::from_secs_f64(1.0 / FRAME_RATE),
+            );
+            interval.set_value(handle.ok());
+        });
+
+        Effect::new(move || {
+            if let Some(handle) = interval.get_value() {
+                handle.clear();
+            }
+            interval.set_value(None);
+            if !playing.get() {
+                return;
+            }
+            if let Some(demo) = demo.borrow().as_ref() {
+                start(demo);
+                return;
+            }
+            if fetching.replace(true) {
+                return;
+            }
+
+            let (demo, fetching, start) = (demo.clone(), fetching.clone(), start.clone());
+            let (developer, game_name) = (developer.clone(), game_name.clone());
+            spawn_local(async move {
+                let fetched = match get_game_demo(developer, game_name).await {
+                    Ok(bytes) => Demo::from_bytes(&bytes).map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                // the speed comes from whoever uploaded the demo
+                let fetched = fetched.map(|mut demo| {
+                    demo.replay.ticks_per_frame =
+                        clamp_ticks_per_frame(demo.replay.ticks_per_frame);
+                    demo
+                });
+                fetching.set(false);
+                match fetched {
+                    Ok(fetched) => {
+                        if playing.get_untracked() {
+                            start(&fetched);
+                        }
+                        *demo.borrow_mut() = Some(fetched);
+                    }
+                    Err(e) => leptos::logging::log!("Error loading game demo: {e}"),
+                }
+            });
+        });
+
+        on_cleanup(move || {
+            if let Some(handle) = interval.try_get_value().flatten() {
+                handle.clear();
+            }
+        });
+    }
+    #[cfg(not(feature = "hydrate"))]
+    let _ = (developer, game_name, profile);
+
+    view! {
+        <canvas
+            class="demo-preview"
+            class:playing=move || playing.get()
+            node_ref=canvas_ref
+            width="128"
+            height="64"
+        />
+    }
+}
@@ -6,7 +6,9 @@ use thaw::{
 
 use crate::{
     app::WalletPublicKeyContext,
-    server::{check_game_is_owned, FetchedGameMetadata},
+    components::DemoPreview,
+    models::OffchainGameMetadata,
+    server::{check_game_is_owned, get_offchain_game_metadata, FetchedGameMetadata},
 };
 
 #[component]
@@ -20,6 +22,27 @@ pub fn GameCard(game: FetchedGameMetadata, refetch_trigger: RwSignal<usize>) ->
     let price_text = format!("{} lamports", game.data.price);
     let supply_text = format!("{} / {}", game.data.current_supply, game.data.max_supply);
 
+    let game_uri = game.data.game_uri;
+    let dev_for_metadata = developer.clone();
+    let name_for_metadata = game_name.clone();
+    let metadata = LocalResource::new(move || {
+        let uri = game_uri.clone();
+        let dev = dev_for_metadata.clone();
+        let name = name_for_metadata.clone();
+        async move {
+            match get_offchain_game_metadata(dev, name).await {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    leptos::logging::log!("Error loading game metadata: {e}");
+                    OffchainGameMetadata::legacy(uri)
+                }
+            }
+        }
+    });
+    let hovering = RwSignal::new(false);
+    let dev_for_demo = developer.clone();
+    let name_for_demo = game_name.clone();
+
     let dev_for_check = developer.clone();
     let name_for_check = game_name.clone();
     let is_owned = LocalResource::new(move || {
@@ -109,7 +132,43 @@ pub fn GameCard(game: FetchedGameMetadata, refetch_trigger: RwSignal<usize>) ->
                 </CardHeaderDescription>
             </CardHeader>
             <p class="game-supply">"Supply: " {supply_text}</p>
-             <Image src={game.data.game_uri} width="200px" height="200px" shape=ImageShape::Rounded/>
+            <Suspense fallback=|| {
+                view! { <Spinner size=SpinnerSize::Small /> }
+            }>
+                {move || {
+                    metadata
+                        .get()
+                        .map(|metadata| {
+                            let demo = metadata
+                                .demo
+                                .map(|_| {
+                                    view! {
+                                        <DemoPreview
+                                            developer=dev_for_demo.clone()
+                                            game_name=name_for_demo.clone()
+                                            profile=metadata.machine
+                                            playing=hovering
+                                        />
+                                    }
+                                });
+                            view! {
+                                <div
+                                    class="game-cover"
+                                    on:mouseenter=move |_| hovering.set(true)
+                                    on:mouseleave=move |_| hovering.set(false)
+                                >
+                                    <Image
+                                        src=metadata.image
+                                        width="200px"
+                                        height="200px"
+                                        shape=ImageShape::Rounded
+                                    />
+                                    {demo}
+                                </div>
+                            }
+                        })
+                }}
+            </Suspense>
             <Show when=move || public_key.get().is_some()>
                 <CardFooter>
                     <Suspense fallback=|| {
@@ -1,9 +1,10 @@
+use crate::components::MachineProfileForm;
 use crate::error::{AppError, AppResult};
 use crate::server::{
     build_allocate_game_account_tx, build_finalize_game_upload_tx, build_upload_game_chunk_tx,
-    encrypt_game_data, upload_game_metadata,
+    upload_game_metadata,
 };
-use base64::{engine::general_purpose::STANDARD, Engine};
+use crate::vm::MachineProfile;
 use leptos::{prelude::*, task::spawn_local};
 use thaw::{
     Button, ButtonAppearance, FileList, Input, Toast, ToastBody, ToastIntent, ToastOptions,
@@ -22,6 +23,12 @@ pub fn GameUpload() -> impl IntoView {
     let image_bytes: StoredValue<Option<Vec<u8>>> = StoredValue::new(None);
     let image_content_type: StoredValue<Option<String>> = StoredValue::new(None);
     let image_loaded = RwSignal::new(false);
+    // optional attract-mode demo: a ROM anyone may download, and a replay recorded on it
+    let demo_rom: StoredValue<Option<Vec<u8>>> = StoredValue::new(None);
+    let demo_rom_loaded = RwSignal::new(false);
+    let demo_replay: StoredValue<Option<Vec<u8>>> = StoredValue::new(None);
+    let demo_replay_loaded = RwSignal::new(false);
+    let machine_profile = RwSignal::new(MachineProfile::default());
     let status = RwSignal::new(String::new());
     let uploading = RwSignal::new(false);
     let toaster = ToasterInjection::expect_context();
@@ -66,6 +73,40 @@ pub fn GameUpload() -> impl IntoView {
         }
     };
 
+    let handle_demo_rom = move |file_list: FileList| {
+        if let Some(file) = file_list.get(0) {
+            let file = file.to_owned();
+            spawn_local(async move {
+                let Ok(array_buffer) = JsFuture::from(file.array_buffer()).await else {
+                    leptos::logging::log!("Failed to read demo ROM");
+                    status.set("Failed to read demo ROM.".into());
+                    return;
+                };
+                let bytes = Uint8Array::new(&array_buffer).to_vec();
+                status.set(format!("Demo ROM loaded: {} bytes", bytes.len()));
+                demo_rom.set_value(Some(bytes));
+                demo_rom_loaded.set(true);
+            });
+        }
+    };
+
+    let handle_demo_replay = move |file_list: FileList| {
+        if let Some(file) = file_list.get(0) {
+            let file = file.to_owned();
+            spawn_local(async move {
+                let Ok(array_buffer) = JsFuture::from(file.array_buffer()).await else {
+                    leptos::logging::log!("Failed to read demo replay");
+                    status.set("Failed to read demo replay.".into());
+                    return;
+                };
+                let bytes = Uint8Array::new(&array_buffer).to_vec();
+                status.set(format!("Demo replay loaded: {} bytes", bytes.len()));
+                demo_replay.set_value(Some(bytes));
+                demo_replay_loaded.set(true);
+            });
+        }
+    };
+
     let on_submit = move |_| {
         uploading.set(true);
         status.set("Starting upload...".into());
@@ -75,9 +116,12 @@ pub fn GameUpload() -> impl IntoView {
                 game_name.get_untracked(),
                 game_price.get_untracked(),
                 max_supply.get_untracked(),
+                machine_profile.get_untracked(),
                 file_bytes,
                 image_bytes,
                 image_content_type,
+                demo_rom,
+                demo_replay,
                 status,
             )
             .await;
@@ -104,6 +148,10 @@ pub fn GameUpload() -> impl IntoView {
                     image_bytes.set_value(None);
                     image_content_type.set_value(None);
                     image_loaded.set(false);
+                    demo_rom.set_value(None);
+                    demo_rom_loaded.set(false);
+                    demo_replay.set_value(None);
+                    demo_replay_loaded.set(false);
                 }
                 Err(e) => {
                     leptos::logging::log!("Upload error: {e}");
@@ -120,6 +168,7 @@ pub fn GameUpload() -> impl IntoView {
             <Input value=game_name placeholder="Game Name" />
             <Input value=game_price placeholder="Price (lamports)" />
             <Input value=max_supply placeholder="Max Supply" />
+            <MachineProfileForm profile=machine_profile />
 
             {move || {
                 if image_loaded.get() {
@@ -147,6 +196,36 @@ pub fn GameUpload() -> impl IntoView {
                 }
             }}
 
+            <h3>"Attract-mode demo (optional)"</h3>
+            <p class="file-status">
+                "Played on the store card on hover. The demo ROM is public, so upload a cut-down build "
+                "and a replay recorded on it in the ROM sandbox."
+            </p>
+            {move || {
+                if demo_rom_loaded.get() {
+                    view! { <p class="file-status">"Demo ROM ready for upload"</p> }.into_any()
+                } else {
+                    view! {
+                        <Upload custom_request=handle_demo_rom>
+                            <UploadDragger>"Click or drag a demo ROM"</UploadDragger>
+                        </Upload>
+                    }
+                        .into_any()
+                }
+            }}
+            {move || {
+                if demo_replay_loaded.get() {
+                    view! { <p class="file-status">"Demo replay ready for upload"</p> }.into_any()
+                } else {
+                    view! {
+                        <Upload custom_request=handle_demo_replay>
+                            <UploadDragger>"Click or drag a demo replay"</UploadDragger>
+                        </Upload>
+                    }
+                        .into_any()
+                }
+            }}
+
             <p class="upload-status">{move || status.get()}</p>
 
             <Button
@@ -185,13 +264,20 @@ async fn upload_game_flow(
     game_name: String,
     game_price: String,
     max_supply: String,
+    machine_profile: MachineProfile,
     file_bytes: StoredValue<Option<Vec<u8>>>,
     image_bytes: StoredValue<Option<Vec<u8>>>,
     image_content_type: StoredValue<Option<String>>,
+    demo_rom: StoredValue<Option<Vec<u8>>>,
+    demo_replay: StoredValue<Option<Vec<u8>>>,
     status: RwSignal<String>,
 ) -> AppResult<()> {
+    use crate::auth::Purpose;
+    use crate::models::Encryptor;
     use crate::server::create_game;
-    use crate::wallet::{get_public_key, send_transaction, sign_message, Message};
+    use crate::vm::{Demo, Replay, MAX_DEMO_BYTES};
+    use crate::wallet::{ensure_signed_in, send_transaction, sign_game_grant};
+    use solana_pubkey::Pubkey;
 
     let bytes = file_bytes
         .get_value()
@@ -203,40 +289,50 @@ async fn upload_game_flow(
     let supply: u64 = max_supply
         .parse()
         .map_err(|_| AppError::custom("Invalid max supply"))?;
+    machine_profile
+        .validate()
+        .map_err(|e| AppError::custom(format!("Invalid machine profile: {e}")))?;
 
-    let developer = get_public_key().await;
+    // checked before anything is signed, since a demo needs both halves to play
+    let demo = match (demo_rom.get_value(), demo_replay.get_value()) {
+        (Some(rom), Some(replay)) => {
+            let demo = Replay::from_bytes(&replay).and_then(|replay| Demo::new(rom, replay));
+            let demo = demo.map_err(|e| AppError::custom(format!("Invalid demo: {e}")))?;
+            let demo_bytes = demo.to_bytes();
+            if demo_bytes.len() > MAX_DEMO_BYTES {
+                return Err(AppError::custom(format!(
+                    "Demo is larger than {} KiB, record a shorter replay",
+                    MAX_DEMO_BYTES / 1024
+                )));
+            }
+            Some(bs58::encode(demo_bytes).into_string())
+        }
+        (None, None) => None,
+        _ => {
+            return Err(AppError::custom(
+                "A demo needs both a ROM and a replay recorded on it",
+            ))
+        }
+    };
 
-    // 0. Register game with backend
+    // 0. Register game with backend, under the wallet this session signed in with
+    status.set("Signing in...".into());
+    let developer = ensure_signed_in(Purpose::Publish).await?;
     status.set("Registering game...".into());
-    let valid_period = (js_sys::Date::now() / 1000.0) as i64 + 7200; // now + 2 hours
-    let (signature, _msg) = sign_message(Message {
-        valid_period,
-        game_name: game_name.clone(),
-    })
-    .await?;
-    let create_game_reponse = create_game(
-        game_name.clone(),
-        developer.clone(),
-        signature,
-        valid_period,
-    )
-    .await
-    .map_err(|e| AppError::custom(e.to_string()))?;
+    let grant = sign_game_grant(&game_name).await?;
+    let create_game_reponse = create_game(game_name.clone(), grant)
+        .await
+        .map_err(|e| AppError::custom(e.to_string()))?;
 
-    // 1. Encrypt game bytes on server
+    // 1. Encrypt game bytes in the browser, so the server never sees the ROM
     status.set("Encrypting game data...".into());
-    let encrypted_b64 = encrypt_game_data(
-        create_game_reponse.encryption_key,
-        create_game_reponse.nonce,
-        STANDARD.encode(&bytes),
-    )
-    .await
-    .map_err(|e| AppError::custom(e.to_string()))?;
-    let bytes = STANDARD
-        .decode(&encrypted_b64)
-        .map_err(|e| AppError::custom(format!("Base64 decode error: {e}")))?;
+    let developer_key: Pubkey = developer
+        .parse()
+        .map_err(|_| AppError::custom("Invalid developer key"))?;
+    let bytes =
+        Encryptor::try_from(create_game_reponse)?.encrypt(&developer_key, &game_name, &bytes)?;
 
-    // 2. Upload image and create metadata URI
+    // 2. Upload image and the metadata document (with the machine profile) it belongs to
     status.set("Uploading game image...".into());
     let img_bytes = image_bytes
         .get_value()
@@ -245,9 +341,15 @@ async fn upload_game_flow(
         .get_value()
         .unwrap_or_else(|| "image/png".to_string());
     let image_b58 = bs58::encode(&img_bytes).into_string();
-    let game_uri = upload_game_metadata(image_b58, content_type)
-        .await
-        .map_err(|e| AppError::custom(e.to_string()))?;
+    let game_uri = upload_game_metadata(
+        game_name.clone(),
+        image_b58,
+        content_type,
+        machine_profile,
+        demo,
+    )
+    .await
+    .map_err(|e| AppError::custom(e.to_string()))?;
 
     let game_hash = sha256(&bytes).await?;
 
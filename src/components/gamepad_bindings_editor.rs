@@ -0,0 +1,87 @@
+use crate::vm::{GamepadLayout, KEYPAD_ORDER};
+use leptos::prelude::*;
+use thaw::Button;
+
+/// Lets a player bind controller buttons and stick directions to the CHIP-8 keys.
+#[component]
+pub fn GamepadBindingsEditor(layout: RwSignal<GamepadLayout>) -> impl IntoView {
+    let listening = RwSignal::new(None::<u8>);
+
+    // gamepads have no input events, so wait for the next input by polling
+    #[cfg(feature = "hydrate")]
+    {
+        use crate::vm::connected_gamepads;
+        use std::time::Duration;
+
+        let interval = StoredValue::new(None::<IntervalHandle>);
+        Effect::new(move || {
+            if let Some(handle) = interval.get_value() {
+                handle.clear();
+            }
+            interval.set_value(None);
+            let Some(button) = listening.get() else {
+                return;
+            };
+            let handle = set_interval_with_handle(
+                move || {
+                    let gamepads = connected_gamepads();
+                    if let Some(input) = gamepads.iter().find_map(|state| state.first_active()) {
+                        layout.update(|layout| layout.rebind(button, input));
+                        listening.set(None);
+                    }
+                },
+                Duration::from_millis(50),
+            );
+            interval.set_value(handle.ok());
+        });
+        on_cleanup(move || {
+            if let Some(handle) = interval.try_get_value().flatten() {
+                handle.clear();
+            }
+        });
+    }
+
+    view! {
+        <div class="key-bindings">
+            <p>
+                {move || match listening.get() {
+                    Some(button) => format!("Press a button or move a stick for {button:X}"),
+                    None => "Press any controller button if yours isn't picked up".into(),
+                }}
+            </p>
+            <ul>
+                {KEYPAD_ORDER
+                    .into_iter()
+                    .map(|button| {
+                        let inputs = move || {
+                            layout
+                                .with(|layout| {
+                                    let inputs = layout.inputs_for(button).map(|i| i.to_string());
+                                    inputs.collect::<Vec<_>>().join(", ")
+                                })
+                        };
+                        view! {
+                            <li class:listening=move || listening.get() == Some(button)>
+                                <span class="key-bindings-button">{format!("{button:X}")}</span>
+                                <span class="key-bindings-codes">{inputs}</span>
+                                <Button on_click=move |_| listening.set(Some(button))>"Rebind"</Button>
+                            </li>
+                        }
+                    })
+                    .collect_view()}
+            </ul>
+            <Button
+                on_click=move |_| listening.set(None)
+                disabled=Signal::derive(move || listening.get().is_none())
+            >
+                "Cancel"
+            </Button>
+            <Button
+                on_click=move |_| layout.set(GamepadLayout::default())
+                disabled=Signal::derive(move || layout.with(|l| *l == GamepadLayout::default()))
+            >
+                "Reset to defaults"
+            </Button>
+        </div>
+    }
+}
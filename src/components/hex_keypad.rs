@@ -0,0 +1,55 @@
+use crate::vm::{code_label, KeyLayout, KEYPAD_ORDER};
+use leptos::prelude::*;
+
+/// Clickable 4x4 keypad for touch screens; `pressed` has a bit set per held CHIP-8 key.
+#[component]
+pub fn HexKeypad(
+    pressed: RwSignal<u16>,
+    #[prop(into)] layout: Signal<Option<KeyLayout>>,
+) -> impl IntoView {
+    let press = move |button: u8, down: bool| {
+        pressed.update(|mask| {
+            if down {
+                *mask |= 1 << button;
+            } else {
+                *mask &= !(1 << button);
+            }
+        })
+    };
+
+    view! {
+        <div class="hex-keypad">
+            {KEYPAD_ORDER
+                .into_iter()
+                .map(|button| {
+                    let binding = move || {
+                        layout
+                            .with(|layout| {
+                                layout
+                                    .as_ref()
+                                    .and_then(|layout| layout.codes_for(button).next())
+                                    .map(|code| code_label(code).to_string())
+                            })
+                    };
+                    view! {
+                        <button
+                            type="button"
+                            class:pressed=move || pressed.get() & (1 << button) != 0
+                            on:pointerdown=move |evt| {
+                                evt.prevent_default();
+                                press(button, true);
+                            }
+                            on:pointerup=move |_| press(button, false)
+                            on:pointerleave=move |_| press(button, false)
+                            on:pointercancel=move |_| press(button, false)
+                            on:contextmenu=move |evt| evt.prevent_default()
+                        >
+                            <span class="hex-keypad-digit">{format!("{button:X}")}</span>
+                            <span class="hex-keypad-binding">{binding}</span>
+                        </button>
+                    }
+                })
+                .collect_view()}
+        </div>
+    }
+}
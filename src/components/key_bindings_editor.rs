@@ -0,0 +1,69 @@
+use crate::vm::{code_label, KeyLayout, KEYPAD_ORDER};
+use leptos::prelude::*;
+use thaw::Button;
+
+/// Lets a player bind their own keyboard keys; the next key pressed after "Rebind" is used.
+#[component]
+pub fn KeyBindingsEditor(
+    layout: RwSignal<Option<KeyLayout>>,
+    /// What the game was published with
+    #[prop(into)]
+    default_layout: Signal<Option<KeyLayout>>,
+) -> impl IntoView {
+    let listening = RwSignal::new(None::<u8>);
+
+    let handle = window_event_listener(leptos::ev::keydown, move |evt| {
+        let Some(button) = listening.get_untracked() else {
+            return;
+        };
+        evt.prevent_default();
+        listening.set(None);
+        let code = evt.code();
+        if code != "Escape" {
+            layout.update(|layout| {
+                if let Some(layout) = layout {
+                    layout.rebind(button, &code);
+                }
+            });
+        }
+    });
+    on_cleanup(move || handle.remove());
+
+    view! {
+        <div class="key-bindings">
+            <p>
+                {move || match listening.get() {
+                    Some(button) => format!("Press a key for {button:X} (Esc to cancel)"),
+                    None => "Keys are matched by position, whatever your keyboard layout".into(),
+                }}
+            </p>
+            <ul>
+                {KEYPAD_ORDER
+                    .into_iter()
+                    .map(|button| {
+                        let codes = move || {
+                            layout
+                                .with(|layout| {
+                                    let codes = layout.iter().flat_map(|l| l.codes_for(button));
+                                    codes.map(code_label).collect::<Vec<_>>().join(" ")
+                                })
+                        };
+                        view! {
+                            <li class:listening=move || listening.get() == Some(button)>
+                                <span class="key-bindings-button">{format!("{button:X}")}</span>
+                                <span class="key-bindings-codes">{codes}</span>
+                                <Button on_click=move |_| listening.set(Some(button))>"Rebind"</Button>
+                            </li>
+                        }
+                    })
+                    .collect_view()}
+            </ul>
+            <Button
+                on_click=move |_| layout.set(default_layout.get_untracked())
+                disabled=Signal::derive(move || layout.get() == default_layout.get())
+            >
+                "Reset to game defaults"
+            </Button>
+        </div>
+    }
+}
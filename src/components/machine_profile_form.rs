@@ -0,0 +1,101 @@
+use crate::vm::{KeyLayout, MachineProfile, Platform, Quirks, DEFAULT_TICKS_PER_FRAME, PALETTES};
+use leptos::prelude::*;
+use thaw::{Checkbox, Input, Select};
+
+const PLAYER_PALETTE: &str = "player";
+
+#[component]
+pub fn MachineProfileForm(profile: RwSignal<MachineProfile>) -> impl IntoView {
+    let platform = RwSignal::new(Platform::default().id().to_string());
+    let ticks_per_frame = RwSignal::new(DEFAULT_TICKS_PER_FRAME.to_string());
+    let palette = RwSignal::new(PLAYER_PALETTE.to_string());
+    let key_layout = RwSignal::new(KeyLayout::default().name);
+
+    let defaults = Quirks::default();
+    let shift = RwSignal::new(defaults.shift);
+    let load_store_increment = RwSignal::new(defaults.load_store_increment);
+    let jump_with_vx = RwSignal::new(defaults.jump_with_vx);
+    let vf_reset = RwSignal::new(defaults.vf_reset);
+    let clip = RwSignal::new(defaults.clip);
+    let display_wait = RwSignal::new(defaults.display_wait);
+
+    // Picking a platform resets the quirks to what that platform shipped with
+    Effect::new(move || {
+        let quirks = platform
+            .get()
+            .parse::<Platform>()
+            .unwrap_or_default()
+            .default_quirks();
+        shift.set(quirks.shift);
+        load_store_increment.set(quirks.load_store_increment);
+        jump_with_vx.set(quirks.jump_with_vx);
+        vf_reset.set(quirks.vf_reset);
+        clip.set(quirks.clip);
+        display_wait.set(quirks.display_wait);
+    });
+
+    Effect::new(move || {
+        let palette_name = palette.get();
+        let layout_name = key_layout.get();
+        profile.set(MachineProfile {
+            platform: platform.get().parse::<Platform>().unwrap_or_default(),
+            ticks_per_frame: ticks_per_frame
+                .get()
+                .parse()
+                .unwrap_or(DEFAULT_TICKS_PER_FRAME),
+            quirks: Quirks {
+                shift: shift.get(),
+                load_store_increment: load_store_increment.get(),
+                jump_with_vx: jump_with_vx.get(),
+                vf_reset: vf_reset.get(),
+                clip: clip.get(),
+                display_wait: display_wait.get(),
+            },
+            palette: PALETTES
+                .iter()
+                .find(|(name, _)| *name == palette_name)
+                .map(|(_, colors)| colors.map(String::from)),
+            key_layout: KeyLayout::presets()
+                .into_iter()
+                .find(|layout| layout.name == layout_name)
+                .unwrap_or_default(),
+        });
+    });
+
+    view! {
+        <div class="machine-profile">
+            <h3>"Machine profile"</h3>
+            <Select value=platform>
+                {Platform::ALL
+                    .into_iter()
+                    .map(|p| view! { <option value=p.id()>{p.name()}</option> })
+                    .collect_view()}
+            </Select>
+            <Input value=ticks_per_frame placeholder="Instructions per frame" />
+            <div class="quirks">
+                <Checkbox checked=shift label="Shift VX in place (8xy6/8xyE)" />
+                <Checkbox checked=load_store_increment label="Increment I on load/store (Fx55/Fx65)" />
+                <Checkbox checked=jump_with_vx label="Jump with VX (Bxnn)" />
+                <Checkbox checked=vf_reset label="Reset VF on logic ops (8xy1-8xy3)" />
+                <Checkbox checked=clip label="Clip sprites at screen edges" />
+                <Checkbox checked=display_wait label="Wait for display refresh (Dxyn)" />
+            </div>
+            <Select value=palette>
+                <option value=PLAYER_PALETTE>"Player's choice of palette"</option>
+                {PALETTES
+                    .iter()
+                    .map(|(name, _)| view! { <option value=*name>{*name}</option> })
+                    .collect_view()}
+            </Select>
+            <Select value=key_layout>
+                {KeyLayout::presets()
+                    .into_iter()
+                    .map(|layout| {
+                        let name = layout.name;
+                        view! { <option value=name.clone()>{name}</option> }
+                    })
+                    .collect_view()}
+            </Select>
+        </div>
+    }
+}
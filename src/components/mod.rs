@@ -1,17 +1,29 @@
 mod admin_route;
+mod debugger_panel;
+mod demo_preview;
 mod developer_gate;
 mod footer;
 mod game_card;
 mod game_upload;
+mod gamepad_bindings_editor;
+mod hex_keypad;
+mod key_bindings_editor;
+mod machine_profile_form;
 mod nav;
 mod play_screen;
 mod wallet_button;
 
 pub use admin_route::*;
+pub use debugger_panel::*;
+pub use demo_preview::*;
 pub use developer_gate::*;
 pub use footer::*;
 pub use game_card::*;
 pub use game_upload::*;
+pub use gamepad_bindings_editor::*;
+pub use hex_keypad::*;
+pub use key_bindings_editor::*;
+pub use machine_profile_form::*;
 pub use nav::*;
 pub use play_screen::*;
 pub use wallet_button::*;
@@ -1,22 +1,149 @@
-use crate::server::{decrypt_game_data, get_game_data, get_key};
-use leptos::{prelude::*, task::spawn_local};
+use crate::app::WalletPublicKeyContext;
+use crate::components::{DebuggerPanel, GamepadBindingsEditor, HexKeypad, KeyBindingsEditor};
+use crate::server::{get_game_data, get_game_metadata, get_game_profile, get_key};
+use crate::vm::{
+    DebugCommand, DebugSnapshot, DisplayOptions, GamepadLayout, KeyLayout, MachineProfile,
+    DEFAULT_TICKS_PER_FRAME, FRAME_RATE, MAX_TICKS_PER_FRAME, PALETTES,
+};
+use leptos::{ev::KeyboardEvent, html, prelude::*, task::spawn_local};
+#[cfg(feature = "hydrate")]
+use std::{cell::RefCell, rc::Rc};
+use thaw::{Button, Checkbox, FileList, Select, Slider, Upload};
+use wasm_bindgen_futures::{js_sys::Uint8Array, JsFuture};
 
-const SCALE: usize = 15;
-const TICKS_PER_FRAME: usize = 20;
+const SAVE_SLOTS: [&str; 3] = ["1", "2", "3"];
+const GAME_PALETTE: &str = "game";
+
+#[derive(Debug, Clone, PartialEq)]
+enum EmuRequest {
+    Save,
+    Load,
+    Record,
+    StopRecording,
+    PlayReplay(Vec<u8>),
+    StopReplay,
+}
+
+/// A ROM run straight from disk instead of bought on chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalRom {
+    pub name: String,
+    pub bytes: Vec<u8>,
+    pub profile: MachineProfile,
+}
 
 #[component]
-pub fn GameScreen(game_to_play: RwSignal<String>) -> impl IntoView {
+pub fn GameScreen(
+    game_to_play: RwSignal<String>,
+    #[prop(optional)] local_rom: Option<RwSignal<Option<LocalRom>>>,
+    /// Always offer the debugger, not only to the game's developer
+    #[prop(optional)]
+    debug: bool,
+) -> impl IntoView {
     let status = RwSignal::new(String::new());
+    let halted = RwSignal::new(None::<String>);
+    let muted = RwSignal::new(false);
+    let volume = RwSignal::new(0.5);
+    // "developer|name" of the game currently running
+    let loaded_game = RwSignal::new(None::<String>);
+    let save_slot = RwSignal::new(SAVE_SLOTS[0].to_string());
+    let emu_request = RwSignal::new(None::<EmuRequest>);
+    let recording = RwSignal::new(false);
+    let replaying = RwSignal::new(false);
+    let debugging = RwSignal::new(false);
+    let debug_snapshot = RwSignal::new(None::<DebugSnapshot>);
+    let debug_command = RwSignal::new(None::<DebugCommand>);
+    let memory_address = RwSignal::new("200".to_string());
+    // the player's bindings, and the ones the game was published with
+    let key_layout = RwSignal::new(None::<KeyLayout>);
+    let default_layout = RwSignal::new(None::<KeyLayout>);
+    let show_bindings = RwSignal::new(false);
+    let keypad = RwSignal::new(0u16);
+    let gamepad_layout = RwSignal::new(GamepadLayout::default());
+    let show_gamepad = RwSignal::new(false);
+    // instructions per 60Hz frame
+    let speed = RwSignal::new(DEFAULT_TICKS_PER_FRAME as f64);
+    let paused = RwSignal::new(false);
+    // the player's display settings, and the palette the game was published with
+    let palette = RwSignal::new(GAME_PALETTE.to_string());
+    let phosphor = RwSignal::new(false);
+    let scanlines = RwSignal::new(false);
+    let game_palette = RwSignal::new(None::<[String; 4]>);
+    let screen_ref = NodeRef::<html::Div>::new();
+    let canvas_ref = NodeRef::<html::Canvas>::new();
+
+    let public_key = use_context::<WalletPublicKeyContext>().map(|ctx| ctx.public_key);
+    let can_debug = Signal::derive(move || {
+        debug
+            || loaded_game.with(|game| {
+                let developer = game
+                    .as_ref()
+                    .and_then(|g| g.split_once('|'))
+                    .map(|(d, _)| d);
+                developer.is_some() && public_key.and_then(|key| key.get()).as_deref() == developer
+            })
+    });
+
+    let handle_replay = move |file_list: FileList| {
+        if let Some(file) = file_list.get(0) {
+            let file = file.to_owned();
+            spawn_local(async move {
+                let Ok(array_buffer) = JsFuture::from(file.array_buffer()).await else {
+                    leptos::logging::log!("Failed to read replay file");
+                    status.set("Failed to read replay file.".into());
+                    return;
+                };
+                let bytes = Uint8Array::new(&array_buffer).to_vec();
+                emu_request.set(Some(EmuRequest::PlayReplay(bytes)));
+            });
+        }
+    };
+
+    #[cfg(feature = "hydrate")]
+    let emu: Rc<RefCell<Option<crate::vm::EmuWasm>>> = Rc::new(RefCell::new(None));
+
+    // the keyboard only plays the screen that has focus, so several can share a page
+    let keypress = {
+        #[cfg(feature = "hydrate")]
+        let emu = emu.clone();
+        move |evt: KeyboardEvent, pressed: bool| {
+            #[cfg(feature = "hydrate")]
+            if let Some(e) = emu.borrow_mut().as_mut() {
+                if pressed {
+                    e.resume_audio();
+                }
+                e.keypress(evt, pressed);
+            }
+            #[cfg(not(feature = "hydrate"))]
+            let _ = (evt, pressed);
+        }
+    };
+    let release_keyboard = {
+        #[cfg(feature = "hydrate")]
+        let emu = emu.clone();
+        move || {
+            #[cfg(feature = "hydrate")]
+            if let Some(e) = emu.borrow_mut().as_mut() {
+                e.release_keyboard();
+            }
+        }
+    };
 
     #[cfg(feature = "hydrate")]
     {
-        use std::cell::{Cell, RefCell};
-        use std::rc::Rc;
+        use std::cell::Cell;
         use std::sync::atomic::{AtomicBool, Ordering};
         use std::sync::Arc;
         use wasm_bindgen::prelude::*;
 
-        let emu: Rc<RefCell<Option<crate::vm::EmuWasm>>> = Rc::new(RefCell::new(None));
+        if let Some(layout) = load_gamepad_layout() {
+            gamepad_layout.set(layout);
+        }
+        if let Some(options) = load_display_options() {
+            palette.set(options.palette.unwrap_or_else(|| GAME_PALETTE.to_string()));
+            phosphor.set(options.phosphor);
+            scanlines.set(options.scanlines);
+        }
 
         let running = Arc::new(AtomicBool::new(true));
         let running_cleanup = running.clone();
@@ -24,44 +151,70 @@ pub fn GameScreen(game_to_play: RwSignal<String>) -> impl IntoView {
             running_cleanup.store(false, Ordering::Relaxed);
         });
 
-        if let Some(document) = web_sys::window().and_then(|w| w.document()) {
-            let emu_kd = emu.clone();
-            let keydown_cb = Closure::wrap(Box::new(move |evt: web_sys::KeyboardEvent| {
-                if let Some(e) = emu_kd.borrow_mut().as_mut() {
-                    e.keypress(evt, true);
-                }
-            }) as Box<dyn FnMut(_)>);
-            let _ = document
-                .add_event_listener_with_callback("keydown", keydown_cb.as_ref().unchecked_ref());
-            keydown_cb.forget();
-
-            let emu_ku = emu.clone();
-            let keyup_cb = Closure::wrap(Box::new(move |evt: web_sys::KeyboardEvent| {
-                if let Some(e) = emu_ku.borrow_mut().as_mut() {
-                    e.keypress(evt, false);
-                }
-            }) as Box<dyn FnMut(_)>);
-            let _ = document
-                .add_event_listener_with_callback("keyup", keyup_cb.as_ref().unchecked_ref());
-            keyup_cb.forget();
-        }
-
         // Game loop (no-op when emu is None)
         let f: Rc<RefCell<Option<Closure<dyn FnMut()>>>> = Rc::new(RefCell::new(None));
         let g = f.clone();
         let emu_loop = emu.clone();
         let running_loop = running.clone();
+        let mut scheduler = crate::vm::FrameScheduler::default();
 
         *g.borrow_mut() = Some(Closure::wrap(Box::new(move || {
             if !running_loop.load(Ordering::Relaxed) {
+                // dropping the emulator closes its audio context, and the loop's own
+                // closure is freed once this call has returned
+                emu_loop.borrow_mut().take();
+                let this = f.borrow_mut().take();
+                spawn_local(async move { drop(this) });
                 return;
             }
             if let Some(e) = emu_loop.borrow_mut().as_mut() {
-                for _ in 0..TICKS_PER_FRAME {
-                    e.tick();
+                let gain = if muted.get_untracked() {
+                    0.0
+                } else {
+                    volume.get_untracked()
+                };
+                e.set_volume(gain as f32);
+                e.poll_gamepads();
+                // run as many 60Hz frames as are due, however fast the display refreshes
+                let frames = if paused.get_untracked() {
+                    scheduler.reset();
+                    e.pause_audio();
+                    0
+                } else {
+                    let now = web_sys::window()
+                        .and_then(|w| w.performance())
+                        .map_or(0.0, |p| p.now());
+                    scheduler.due_frames(now)
+                };
+                for _ in 0..frames {
+                    // rewinding can step back out of a halt
+                    if e.halted().is_some() && !e.is_rewinding() {
+                        break;
+                    }
+                    match e.run_frame() {
+                        Ok(()) if halted.get_untracked().is_some() => halted.set(None),
+                        Ok(()) => {}
+                        Err(err) => {
+                            leptos::logging::log!("Emulator halted: {err}");
+                            halted.set(Some(err.to_string()));
+                            break;
+                        }
+                    }
+                }
+                if replaying.get_untracked() && !e.is_replaying() {
+                    replaying.set(false);
+                    status.set("Replay finished".into());
+                }
+                if debugging.get_untracked() {
+                    let base = memory_address.with_untracked(|address| {
+                        usize::from_str_radix(address.trim_start_matches("0x"), 16).unwrap_or(0)
+                    });
+                    let snapshot = e.debug_snapshot(base);
+                    if debug_snapshot.with_untracked(|s| s.as_ref() != Some(&snapshot)) {
+                        debug_snapshot.set(Some(snapshot));
+                    }
                 }
-                e.tick_timers();
-                e.draw_screen(SCALE);
+                e.draw_screen();
             }
             if let Some(window) = web_sys::window() {
                 if let Some(cb) = f.borrow().as_ref() {
@@ -76,8 +229,61 @@ pub fn GameScreen(game_to_play: RwSignal<String>) -> impl IntoView {
             }
         }
 
+        // Load a ROM into a freshly configured emulator
+        let emu_start = emu.clone();
+        let start_game = Rc::new(move |game: String, profile: &MachineProfile, rom: &[u8]| {
+            let data = js_sys::Uint8Array::from(rom);
+            let mut emu_ref = emu_start.borrow_mut();
+            match crate::vm::EmuWasm::new(profile, &canvas_ref) {
+                Some(e) => *emu_ref = Some(e),
+                None => {
+                    leptos::logging::log!("Failed to initialize emulator");
+                    status.set("Something went wrong. Please try again.".into());
+                    return;
+                }
+            }
+            if let Some(e) = emu_ref.as_mut() {
+                halted.set(None);
+                loaded_game.set(None);
+                recording.set(false);
+                replaying.set(false);
+                debug_snapshot.set(None);
+                let layout = load_key_layout(&game).unwrap_or_else(|| profile.key_layout.clone());
+                key_layout.set(Some(layout));
+                e.set_gamepad_layout(gamepad_layout.get_untracked());
+                default_layout.set(Some(profile.key_layout.clone()));
+                game_palette.set(profile.palette.clone());
+                speed.set(crate::vm::clamp_ticks_per_frame(profile.ticks_per_frame) as f64);
+                paused.set(false);
+                if let Some(screen) = screen_ref.get_untracked() {
+                    let _ = screen.focus();
+                }
+                match e.load_game(data) {
+                    Ok(()) => {
+                        loaded_game.set(Some(game));
+                        status.set("Game loaded!".into());
+                    }
+                    Err(err) => {
+                        leptos::logging::log!("Emulator halted: {err}");
+                        halted.set(Some(err.to_string()));
+                        status.set("This game can't be loaded.".into());
+                    }
+                }
+            }
+        });
+
+        if let Some(local_rom) = local_rom {
+            let start = start_game.clone();
+            Effect::new(move || {
+                local_rom.with(|rom| {
+                    if let Some(rom) = rom {
+                        start(format!("local|{}", rom.name), &rom.profile, &rom.bytes);
+                    }
+                });
+            });
+        }
+
         // Fetch ROM when game selection changes
-        let emu_effect = emu.clone();
         let version = Rc::new(Cell::new(0u32));
 
         Effect::new(move || {
@@ -89,42 +295,31 @@ pub fn GameScreen(game_to_play: RwSignal<String>) -> impl IntoView {
             let v = version.get() + 1;
             version.set(v);
             let version_check = version.clone();
-            let emu_clone = emu_effect.clone();
+            let start = start_game.clone();
 
             spawn_local(async move {
                 if let Some((developer, name)) = value.split_once('|') {
-                    use crate::wallet::{get_public_key, sign_message, Message};
-                    use base64::{engine::general_purpose::STANDARD, Engine};
-
-                    // 1. Sign message
-                    status.set("Signing...".into());
-                    let valid_period = (js_sys::Date::now() / 1000.0) as i64 + 7200;
-                    let (signature, _) = match sign_message(Message {
-                        valid_period,
-                        game_name: name.to_string(),
-                    })
-                    .await
-                    {
-                        Ok(r) => r,
-                        Err(e) => {
-                            leptos::logging::log!("Play error: {e}");
-                            status.set("Something went wrong. Please try again.".into());
-                            return;
-                        }
-                    };
+                    use crate::auth::Purpose;
+                    use crate::error::AppError;
+                    use crate::models::Encryptor;
+                    use crate::wallet::{ensure_signed_in, sign_game_grant};
+                    use solana_pubkey::Pubkey;
+
+                    // 1. Sign in, which only prompts the wallet once per session
+                    status.set("Signing in...".into());
+                    if let Err(e) = ensure_signed_in(Purpose::Play).await {
+                        leptos::logging::log!("Play error: {e}");
+                        status.set("Something went wrong. Please try again.".into());
+                        return;
+                    }
 
                     // 2. Get decryption key
                     status.set("Getting decryption key...".into());
-                    let player = get_public_key().await;
-                    let key_response = match get_key(
-                        name.to_string(),
-                        developer.to_string(),
-                        player,
-                        signature,
-                        valid_period,
-                    )
-                    .await
-                    {
+                    let key_response = match sign_game_grant(name).await {
+                        Ok(grant) => get_key(name.to_string(), developer.to_string(), grant).await,
+                        Err(e) => Err(e),
+                    };
+                    let key_response = match key_response {
                         Ok(r) => r,
                         Err(e) => {
                             leptos::logging::log!("Play error: {e}");
@@ -135,7 +330,7 @@ pub fn GameScreen(game_to_play: RwSignal<String>) -> impl IntoView {
 
                     // 3. Fetch encrypted game data from chain
                     status.set("Fetching game data...".into());
-                    let encrypted_bytes =
+                    let game_data =
                         match get_game_data(developer.to_string(), name.to_string()).await {
                             Ok(r) => r,
                             Err(e) => {
@@ -145,23 +340,37 @@ pub fn GameScreen(game_to_play: RwSignal<String>) -> impl IntoView {
                             }
                         };
 
-                    // 4. Decrypt server-side
+                    // 4. Check the data is what the developer published before decrypting it
+                    status.set("Verifying game data...".into());
+                    let metadata =
+                        match get_game_metadata(developer.to_string(), name.to_string()).await {
+                            Ok(r) => r,
+                            Err(e) => {
+                                leptos::logging::log!("Play error: {e}");
+                                status.set("Something went wrong. Please try again.".into());
+                                return;
+                            }
+                        };
+                    if let Err(e) = game_data.verify(&metadata.data.data_hash) {
+                        leptos::logging::log!("Play error: {e:?}");
+                        status.set(e.to_string());
+                        return;
+                    }
+                    let encrypted_bytes = game_data.data;
+
+                    // 5. Decrypt in the browser, the ROM never goes back to the server
                     status.set("Decrypting...".into());
-                    let decrypted_b64 = match decrypt_game_data(
-                        key_response.encryption_key,
-                        key_response.nonce,
-                        STANDARD.encode(&encrypted_bytes),
-                    )
-                    .await
-                    {
-                        Ok(r) => r,
-                        Err(e) => {
-                            leptos::logging::log!("Play error: {e}");
-                            status.set("Something went wrong. Please try again.".into());
-                            return;
-                        }
-                    };
-                    let rom_bytes = match STANDARD.decode(&decrypted_b64) {
+                    let developer_key = developer
+                        .parse::<Pubkey>()
+                        .map_err(|_| AppError::custom("Invalid developer key"));
+                    let decrypted = developer_key.and_then(|developer_key| {
+                        Encryptor::try_from(&key_response)?.decrypt(
+                            &developer_key,
+                            name,
+                            &encrypted_bytes,
+                        )
+                    });
+                    let rom_bytes = match decrypted {
                         Ok(r) => r,
                         Err(e) => {
                             leptos::logging::log!("Play error: {e}");
@@ -170,38 +379,469 @@ pub fn GameScreen(game_to_play: RwSignal<String>) -> impl IntoView {
                         }
                     };
 
-                    // 5. Load into emulator
+                    // 6. Fetch the machine profile the game was published with
+                    status.set("Loading machine profile...".into());
+                    let profile =
+                        match get_game_profile(developer.to_string(), name.to_string()).await {
+                            Ok(r) => r,
+                            Err(e) => {
+                                leptos::logging::log!("Play error: {e}");
+                                status.set("Something went wrong. Please try again.".into());
+                                return;
+                            }
+                        };
+
+                    // 7. Load into a freshly configured emulator
                     if version_check.get() != v {
                         return;
                     }
-                    let data = js_sys::Uint8Array::from(rom_bytes.as_slice());
-                    let mut emu_ref = emu_clone.borrow_mut();
-                    if emu_ref.is_none() {
-                        match crate::vm::EmuWasm::new() {
-                            Some(e) => *emu_ref = Some(e),
-                            None => {
-                                leptos::logging::log!("Failed to initialize emulator");
-                                status.set("Something went wrong. Please try again.".into());
-                                return;
+                    start(value.clone(), &profile, &rom_bytes);
+                }
+            });
+        });
+
+        // Debugger commands, and letting the game run freely once the panel closes
+        let emu_debug = emu.clone();
+        Effect::new(move || {
+            let command = debug_command.get();
+            let open = debugging.get() && can_debug.get();
+            let mut emu_ref = emu_debug.borrow_mut();
+            let Some(e) = emu_ref.as_mut() else {
+                return;
+            };
+            match command {
+                Some(command) if open => {
+                    debug_command.set(None);
+                    e.debug(command);
+                }
+                _ if !open => e.detach_debugger(),
+                _ => {}
+            }
+        });
+
+        // Rebinding keys applies right away and is remembered for this game
+        let emu_keys = emu.clone();
+        Effect::new(move || {
+            let Some(layout) = key_layout.get() else {
+                return;
+            };
+            if let Some(e) = emu_keys.borrow_mut().as_mut() {
+                e.set_key_layout(layout.clone());
+            }
+            let Some(game) = loaded_game.get_untracked() else {
+                return;
+            };
+            let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+            else {
+                return;
+            };
+            let key = key_bindings_key(&game);
+            let saved = if default_layout.get_untracked().as_ref() == Some(&layout) {
+                storage.remove_item(&key)
+            } else {
+                match serde_json::to_string(&layout) {
+                    Ok(json) => storage.set_item(&key, &json),
+                    Err(_) => return,
+                }
+            };
+            if let Err(err) = saved {
+                leptos::logging::log!("Key bindings error: {err:?}");
+            }
+        });
+
+        // The speed slider takes effect on the next frame
+        let emu_speed = emu.clone();
+        Effect::new(move || {
+            let ticks_per_frame = speed.get() as u32;
+            if let Some(e) = emu_speed.borrow_mut().as_mut() {
+                e.set_ticks_per_frame(ticks_per_frame);
+            }
+        });
+
+        // Controller bindings are the player's own, shared by every game
+        let emu_gamepad = emu.clone();
+        Effect::new(move || {
+            use crate::utils::LS_GAMEPAD_LAYOUT;
+
+            let layout = gamepad_layout.get();
+            if let Some(e) = emu_gamepad.borrow_mut().as_mut() {
+                e.set_gamepad_layout(layout.clone());
+            }
+            let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+            else {
+                return;
+            };
+            let saved = if layout == GamepadLayout::default() {
+                storage.remove_item(LS_GAMEPAD_LAYOUT)
+            } else {
+                match serde_json::to_string(&layout) {
+                    Ok(json) => storage.set_item(LS_GAMEPAD_LAYOUT, &json),
+                    Err(_) => return,
+                }
+            };
+            if let Err(err) = saved {
+                leptos::logging::log!("Gamepad bindings error: {err:?}");
+            }
+        });
+
+        // Display settings are the player's own too
+        let emu_display = emu.clone();
+        Effect::new(move || {
+            use crate::utils::LS_DISPLAY_OPTIONS;
+
+            let options = DisplayOptions {
+                palette: Some(palette.get()).filter(|name| name != GAME_PALETTE),
+                phosphor: phosphor.get(),
+                scanlines: scanlines.get(),
+            };
+            if let Some(e) = emu_display.borrow_mut().as_mut() {
+                game_palette
+                    .with(|colors| e.set_render_options(options.render_options(colors.as_ref())));
+            }
+            let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+            else {
+                return;
+            };
+            let saved = if options == DisplayOptions::default() {
+                storage.remove_item(LS_DISPLAY_OPTIONS)
+            } else {
+                match serde_json::to_string(&options) {
+                    Ok(json) => storage.set_item(LS_DISPLAY_OPTIONS, &json),
+                    Err(_) => return,
+                }
+            };
+            if let Err(err) = saved {
+                leptos::logging::log!("Display settings error: {err:?}");
+            }
+        });
+
+        // On-screen keypad presses, applied as changes to the held keys
+        let emu_pad = emu.clone();
+        let applied = Rc::new(Cell::new(0u16));
+        Effect::new(move || {
+            let mask = keypad.get();
+            let changed = mask ^ applied.replace(mask);
+            if let Some(e) = emu_pad.borrow_mut().as_mut() {
+                if mask != 0 {
+                    e.resume_audio();
+                }
+                for button in (0..16).filter(|button| changed & (1 << button) != 0) {
+                    e.press_button(button, mask & (1 << button) != 0);
+                }
+            }
+        });
+
+        // Save states and replays for the running game
+        let emu_state = emu.clone();
+        Effect::new(move || {
+            use base64::{engine::general_purpose::STANDARD, Engine};
+
+            let Some(request) = emu_request.get() else {
+                return;
+            };
+            emu_request.set(None);
+            let Some(game) = loaded_game.get_untracked() else {
+                return;
+            };
+            let mut emu_ref = emu_state.borrow_mut();
+            let Some(e) = emu_ref.as_mut() else {
+                return;
+            };
+            let slot = save_slot.get_untracked();
+            let storage = web_sys::window().and_then(|w| w.local_storage().ok().flatten());
+            let key = save_slot_key(&game, &slot);
+
+            match request {
+                EmuRequest::Save => {
+                    let Some(storage) = storage else {
+                        status.set("Local storage isn't available.".into());
+                        return;
+                    };
+                    match storage.set_item(&key, &STANDARD.encode(e.save_state())) {
+                        Ok(()) => status.set(format!("Saved to slot {slot}")),
+                        Err(err) => {
+                            leptos::logging::log!("Save error: {err:?}");
+                            status.set("Couldn't save the game.".into());
+                        }
+                    }
+                }
+                EmuRequest::Load => {
+                    let Some(storage) = storage else {
+                        status.set("Local storage isn't available.".into());
+                        return;
+                    };
+                    let bytes = match storage.get_item(&key) {
+                        Ok(Some(encoded)) => STANDARD.decode(encoded).ok(),
+                        _ => {
+                            status.set(format!("Slot {slot} is empty"));
+                            return;
+                        }
+                    };
+                    match bytes.map(|bytes| e.load_state(&bytes)) {
+                        Some(Ok(())) => {
+                            halted.set(None);
+                            status.set(format!("Loaded slot {slot}"));
+                        }
+                        Some(Err(err)) => {
+                            leptos::logging::log!("Load error: {err}");
+                            status.set("This save can't be loaded.".into());
+                        }
+                        None => status.set("This save can't be loaded.".into()),
+                    }
+                }
+                EmuRequest::Record => match e.start_recording() {
+                    Ok(()) => {
+                        halted.set(None);
+                        recording.set(true);
+                        status.set("Recording...".into());
+                    }
+                    Err(err) => {
+                        leptos::logging::log!("Record error: {err}");
+                        halted.set(Some(err.to_string()));
+                    }
+                },
+                EmuRequest::StopRecording => {
+                    recording.set(false);
+                    if let Some(replay) = e.stop_recording() {
+                        let name = game.split_once('|').map_or(game.as_str(), |(_, n)| n);
+                        match download_replay(name, &replay.to_bytes()) {
+                            Ok(()) => status
+                                .set(format!("Recorded {:.0}s replay", replay.duration_secs())),
+                            Err(err) => {
+                                leptos::logging::log!("Export error: {err:?}");
+                                status.set("Couldn't export the replay.".into());
                             }
                         }
                     }
-                    if let Some(e) = emu_ref.as_mut() {
-                        e.reset();
-                        e.load_game(data);
-                        status.set("Game loaded!".into());
+                }
+                EmuRequest::PlayReplay(bytes) => {
+                    match crate::vm::Replay::from_bytes(&bytes).and_then(|r| e.play_replay(r)) {
+                        Ok(()) => {
+                            halted.set(None);
+                            recording.set(false);
+                            replaying.set(true);
+                            status.set("Replaying...".into());
+                        }
+                        Err(err) => {
+                            leptos::logging::log!("Replay error: {err}");
+                            status.set(format!("This replay can't be played: {err}"));
+                        }
                     }
                 }
-            });
+                EmuRequest::StopReplay => {
+                    e.stop_replay();
+                    replaying.set(false);
+                    status.set("Replay stopped".into());
+                }
+            }
         });
     }
 
     view! {
         <p class="play-status">{move || status.get()}</p>
-        <canvas
-            id="canvas"
-            width="960"
-            height="480"
-        />
+        {move || {
+            halted
+                .get()
+                .map(|message| {
+                    view! { <p class="play-halted">"Emulator halted: " {message}</p> }
+                })
+        }}
+        <div class="play-stage">
+            <div
+                class="play-screen"
+                tabindex="0"
+                node_ref=screen_ref
+                on:keydown={
+                    let keypress = keypress.clone();
+                    move |evt| keypress(evt, true)
+                }
+                on:keyup=move |evt| keypress(evt, false)
+                on:blur=move |_| release_keyboard()
+            >
+                <canvas node_ref=canvas_ref width="1024" height="512" />
+            </div>
+            <Show when=move || debugging.get() && can_debug.get()>
+                <DebuggerPanel
+                    snapshot=debug_snapshot
+                    command=debug_command
+                    memory_address=memory_address
+                />
+            </Show>
+        </div>
+        <HexKeypad pressed=keypad layout=key_layout />
+        <p class="play-hint">"Click the screen to play with the keyboard, hold Backspace to rewind"</p>
+        <Show when=move || show_bindings.get()>
+            <KeyBindingsEditor layout=key_layout default_layout=default_layout />
+        </Show>
+        <Show when=move || show_gamepad.get()>
+            <GamepadBindingsEditor layout=gamepad_layout />
+        </Show>
+        <div class="play-states">
+            <Select value=save_slot>
+                {SAVE_SLOTS
+                    .iter()
+                    .map(|slot| view! { <option value=*slot>"Slot " {*slot}</option> })
+                    .collect_view()}
+            </Select>
+            <Button
+                on_click=move |_| emu_request.set(Some(EmuRequest::Save))
+                disabled=Signal::derive(move || loaded_game.get().is_none())
+            >
+                "Save"
+            </Button>
+            <Button
+                on_click=move |_| emu_request.set(Some(EmuRequest::Load))
+                disabled=Signal::derive(move || {
+                    loaded_game.get().is_none() || recording.get() || replaying.get()
+                })
+            >
+                "Load"
+            </Button>
+        </div>
+        <div class="play-replay">
+            {move || {
+                if recording.get() {
+                    view! {
+                        <Button on_click=move |_| {
+                            emu_request.set(Some(EmuRequest::StopRecording))
+                        }>"Stop & export"</Button>
+                    }
+                        .into_any()
+                } else if replaying.get() {
+                    view! {
+                        <Button on_click=move |_| {
+                            emu_request.set(Some(EmuRequest::StopReplay))
+                        }>"Stop replay"</Button>
+                    }
+                        .into_any()
+                } else {
+                    view! {
+                        <Button
+                            on_click=move |_| emu_request.set(Some(EmuRequest::Record))
+                            disabled=Signal::derive(move || loaded_game.get().is_none())
+                        >
+                            "Record"
+                        </Button>
+                        <Upload custom_request=handle_replay>
+                            <Button disabled=Signal::derive(move || {
+                                loaded_game.get().is_none()
+                            })>"Open replay"</Button>
+                        </Upload>
+                    }
+                        .into_any()
+                }
+            }}
+        </div>
+        <div class="play-speed">
+            <Button
+                on_click=move |_| paused.update(|paused| *paused = !*paused)
+                disabled=Signal::derive(move || loaded_game.get().is_none())
+            >
+                {move || if paused.get() { "Resume" } else { "Pause" }}
+            </Button>
+            <span>"Speed"</span>
+            <Slider value=speed min=1.0 max=MAX_TICKS_PER_FRAME as f64 step=1.0 />
+            <span>
+                {move || format!("{:.0} instructions/s", speed.get() * FRAME_RATE)}
+            </span>
+        </div>
+        <div class="play-display">
+            <Select value=palette>
+                <option value=GAME_PALETTE>"Game's palette"</option>
+                {PALETTES
+                    .iter()
+                    .map(|(name, _)| view! { <option value=*name>{*name}</option> })
+                    .collect_view()}
+            </Select>
+            <Checkbox checked=phosphor label="Phosphor fade" />
+            <Checkbox checked=scanlines label="Scanlines" />
+            <Button on_click=move |_| {
+                if let Some(screen) = screen_ref.get() {
+                    let _ = screen.request_fullscreen();
+                }
+            }>"Fullscreen"</Button>
+        </div>
+        <div class="play-audio">
+            <Show when=move || can_debug.get()>
+                <Checkbox checked=debugging label="Debugger" />
+            </Show>
+            <Checkbox checked=show_bindings label="Key bindings" />
+            <Checkbox checked=show_gamepad label="Controller" />
+            <Checkbox checked=muted label="Mute" />
+            <Slider value=volume max=1.0 step=0.05 />
+        </div>
     }
 }
+
+// local storage key for a save slot, scoped to developer + game name
+#[cfg(feature = "hydrate")]
+fn save_slot_key(game: &str, slot: &str) -> String {
+    use crate::utils::LS_SAVE_STATE_PREFIX;
+
+    let (developer, name) = game.split_once('|').unwrap_or(("", game));
+    format!("{LS_SAVE_STATE_PREFIX}:{developer}:{name}:{slot}")
+}
+
+// local storage key for the player's own key bindings for a game
+#[cfg(feature = "hydrate")]
+fn key_bindings_key(game: &str) -> String {
+    use crate::utils::LS_KEY_BINDINGS_PREFIX;
+
+    let (developer, name) = game.split_once('|').unwrap_or(("", game));
+    format!("{LS_KEY_BINDINGS_PREFIX}:{developer}:{name}")
+}
+
+#[cfg(feature = "hydrate")]
+fn load_key_layout(game: &str) -> Option<KeyLayout> {
+    let storage = web_sys::window()?.local_storage().ok()??;
+    let json = storage.get_item(&key_bindings_key(game)).ok()??;
+    serde_json::from_str(&json).ok()
+}
+
+#[cfg(feature = "hydrate")]
+fn load_gamepad_layout() -> Option<GamepadLayout> {
+    use crate::utils::LS_GAMEPAD_LAYOUT;
+
+    let storage = web_sys::window()?.local_storage().ok()??;
+    let json = storage.get_item(LS_GAMEPAD_LAYOUT).ok()??;
+    serde_json::from_str(&json).ok()
+}
+
+#[cfg(feature = "hydrate")]
+fn load_display_options() -> Option<DisplayOptions> {
+    use crate::utils::LS_DISPLAY_OPTIONS;
+
+    let storage = web_sys::window()?.local_storage().ok()??;
+    let json = storage.get_item(LS_DISPLAY_OPTIONS).ok()??;
+    serde_json::from_str(&json).ok()
+}
+
+// save replay bytes through a temporary download link
+#[cfg(feature = "hydrate")]
+fn download_replay(game_name: &str, bytes: &[u8]) -> Result<(), wasm_bindgen::JsValue> {
+    use crate::vm::REPLAY_FILE_EXTENSION;
+    use wasm_bindgen::JsCast;
+    use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or("No document")?;
+    let parts = js_sys::Array::of1(&Uint8Array::from(bytes));
+    let options = BlobPropertyBag::new();
+    options.set_type("application/octet-stream");
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+
+    let anchor: HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download(&format!("{game_name}.{REPLAY_FILE_EXTENSION}"));
+    anchor.click();
+    // revoking straight away can cancel the download before the browser has started it
+    set_timeout(
+        move || {
+            let _ = Url::revoke_object_url(&url);
+        },
+        std::time::Duration::from_secs(60),
+    );
+    Ok(())
+}
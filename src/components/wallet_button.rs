@@ -26,10 +26,10 @@ pub fn WalletButton() -> impl IntoView {
     let disconnect_action = Action::new_unsync(move |_| async move {
         #[cfg(feature = "hydrate")]
         {
-            crate::wallet::disconnect_phantom()
-                .await
-                .and_then(|_| Ok(set_public_key.set(None)))
-                .map_err(AppError::from)
+            crate::wallet::disconnect_phantom().await?;
+            set_public_key.set(None);
+            // the session belongs to the wallet, so it ends with it
+            crate::server::sign_out().await
         }
         #[cfg(not(feature = "hydrate"))]
         {
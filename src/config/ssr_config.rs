@@ -1,13 +1,15 @@
 #[cfg(feature = "ssr")]
 pub mod config {
     use crate::{
+        auth::BackendAuth,
         error::{AppError, AppResult},
-        utils::{deserialize_address, deserialize_keypair},
+        utils::{deserialize_address, deserialize_keypair, deserialize_optional_keypair},
     };
     use serde::Deserialize;
     use solana_pubkey::Pubkey;
 
     pub const DEFAULT_CONFIG_FILE: &'static str = "config/config.toml";
+    const CLUSTERS: [&str; 3] = ["mainnet-beta", "devnet", "testnet"];
 
     #[derive(Clone, Deserialize)]
     pub struct Config {
@@ -18,6 +20,8 @@ pub mod config {
     #[derive(Clone, Deserialize)]
     pub struct SolanaConfig {
         pub rpc_url: String,
+        /// `mainnet-beta`, `devnet` or `testnet`; sign-in messages are bound to it
+        pub cluster: String,
         #[serde(deserialize_with = "deserialize_address")]
         pub program_id: Pubkey,
         #[serde(deserialize_with = "deserialize_keypair")]
@@ -27,21 +31,54 @@ pub mod config {
 
     #[derive(Clone, Deserialize)]
     pub struct AppConfig {
+        /// Host the site is served from, which sign-in messages are bound to
+        pub domain: String,
         pub backend_url: String,
         #[serde(deserialize_with = "deserialize_address")]
         pub backend_signer: Pubkey,
+        #[serde(default)]
+        pub backend_auth: BackendAuth,
+        /// Signs this server's requests to the backend, which knows its public key. Only
+        /// used with `backend_auth = "server"`.
+        #[serde(default, deserialize_with = "deserialize_optional_keypair")]
+        pub backend_keypair: Option<[u8; 64]>,
     }
 
     pub async fn load_config() -> AppResult<Config> {
         let config_file = std::env::var("CONFIG_DIR").unwrap_or(String::from(DEFAULT_CONFIG_FILE));
 
-        let content = tokio::fs::read_to_string(config_file)
+        let content = tokio::fs::read_to_string(&config_file)
             .await
             .map_err(|e| AppError::custom(e.to_string()))?;
 
-        let config =
-            toml::from_str::<Config>(&content).map_err(|e| AppError::custom(e.to_string()))?;
+        let config = toml::from_str::<Config>(&content)
+            .map_err(|e| AppError::custom(format!("Invalid config {config_file}: {e}")))?;
+        config.check()?;
 
         Ok(config)
     }
+
+    impl Config {
+        /// Settings the server can't run without, checked before it starts
+        fn check(&self) -> AppResult<()> {
+            if self.app.domain.is_empty() || self.app.domain.contains('/') {
+                return Err(AppError::custom(format!(
+                    "app.domain must be the host the site is served from, e.g. \"replayer.example\", not {:?}",
+                    self.app.domain
+                )));
+            }
+            if !CLUSTERS.contains(&self.solana.cluster.as_str()) {
+                return Err(AppError::custom(format!(
+                    "solana.cluster must be one of {CLUSTERS:?}, not {:?}",
+                    self.solana.cluster
+                )));
+            }
+            if self.app.backend_auth == BackendAuth::Server && self.app.backend_keypair.is_none() {
+                return Err(AppError::custom(
+                    "app.backend_keypair is required with backend_auth = \"server\"",
+                ));
+            }
+            Ok(())
+        }
+    }
 }
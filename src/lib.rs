@@ -1,6 +1,7 @@
 #![recursion_limit = "256"]
 
 pub mod app;
+mod auth;
 mod components;
 pub mod config;
 pub mod error;
@@ -9,7 +10,7 @@ mod models;
 mod pages;
 mod server;
 mod utils;
-mod vm;
+pub mod vm;
 mod wallet;
 pub use generated::*;
 
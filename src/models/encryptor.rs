@@ -1,42 +1,35 @@
 use aes_gcm::{
-    aead::{consts::U12, generic_array::GenericArray},
+    aead::{consts::U12, generic_array::GenericArray, OsRng, Payload},
     Aes256Gcm, Key,
 };
 use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_pubkey::Pubkey;
 
 use crate::{
     error::{AppError, AppResult},
-    server::CreateGameResponse,
+    server::{CreateGameResponse, KeyResponse},
 };
-use aes_gcm::aead::Aead;
+use aes_gcm::aead::{Aead, AeadCore};
 use aes_gcm::KeyInit;
+
+/// First byte of the envelope [`Encryptor::encrypt`] produces
+pub const ENVELOPE_VERSION: u8 = 1;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// AES-256-GCM over a whole ROM. Runs in the browser, so ROMs never pass through the app
+/// server. The key itself does: `get_key` and `create_game` relay it from the backend.
 pub struct Encryptor {
     pub encryption_key: Key<Aes256Gcm>,
+    /// Backend-issued nonce, only used to open raw ciphertexts from before envelopes
     pub nonce: GenericArray<u8, U12>,
 }
 
 impl Encryptor {
-    pub fn encrypt(&self, plaintext: &[u8]) -> AppResult<Vec<u8>> {
-        let cipher = Aes256Gcm::new(&self.encryption_key);
-        cipher
-            .encrypt(&self.nonce, plaintext)
-            .map_err(|e| AppError::custom(format!("Encrypt error: {}", e)))
-    }
-
-    pub fn decrypt(&self, ciphertext: &[u8]) -> AppResult<Vec<u8>> {
-        let cipher = Aes256Gcm::new(&self.encryption_key);
-        cipher
-            .decrypt(&self.nonce, ciphertext)
-            .map_err(|e| AppError::custom(format!("Decrypt error: {}", e)))
-    }
-}
-
-impl TryFrom<CreateGameResponse> for Encryptor {
-    type Error = AppError;
-
-    fn try_from(dto: CreateGameResponse) -> Result<Self, Self::Error> {
+    /// From the base64 key and nonce the backend hands out
+    pub fn from_base64(encryption_key: &str, nonce: &str) -> AppResult<Self> {
         let nonce_bytes: [u8; 12] = STANDARD
-            .decode(dto.nonce)
+            .decode(nonce)
             .map_err(|e| AppError::custom(e.to_string()))?
             .try_into()
             .map_err(|_| AppError::custom("Can't deserialize nonce"))?;
@@ -44,7 +37,7 @@ impl TryFrom<CreateGameResponse> for Encryptor {
         let nonce = GenericArray::from_slice(&nonce_bytes).to_owned();
 
         let encryption_key_bytes: [u8; 32] = STANDARD
-            .decode(dto.encryption_key)
+            .decode(encryption_key)
             .map_err(|e| AppError::custom(e.to_string()))?
             .try_into()
             .map_err(|_| AppError::custom("Can't deserialize encryption key"))?;
@@ -56,4 +49,117 @@ impl TryFrom<CreateGameResponse> for Encryptor {
             nonce,
         })
     }
+
+    /// Seals `plaintext` as version byte, fresh random nonce and ciphertext. The developer
+    /// and game name are authenticated alongside it, so the ciphertext can't be passed off
+    /// as another game.
+    pub fn encrypt(
+        &self,
+        developer: &Pubkey,
+        game_name: &str,
+        plaintext: &[u8],
+    ) -> AppResult<Vec<u8>> {
+        let cipher = Aes256Gcm::new(&self.encryption_key);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = associated_data(developer, game_name);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|e| AppError::custom(format!("Encrypt error: {}", e)))?;
+
+        Ok([&[ENVELOPE_VERSION][..], &nonce, &ciphertext].concat())
+    }
+
+    /// Opens an envelope, or a raw ciphertext sealed with the backend's nonce before
+    /// envelopes existed
+    pub fn decrypt(&self, developer: &Pubkey, game_name: &str, data: &[u8]) -> AppResult<Vec<u8>> {
+        let cipher = Aes256Gcm::new(&self.encryption_key);
+        if let Some((&ENVELOPE_VERSION, envelope)) = data.split_first() {
+            if envelope.len() >= NONCE_LEN + TAG_LEN {
+                let (nonce, ciphertext) = envelope.split_at(NONCE_LEN);
+                let aad = associated_data(developer, game_name);
+                let payload = Payload {
+                    msg: ciphertext,
+                    aad: &aad,
+                };
+                if let Ok(plaintext) = cipher.decrypt(GenericArray::from_slice(nonce), payload) {
+                    return Ok(plaintext);
+                }
+            }
+        }
+
+        // a raw ciphertext can start with the version byte by chance, so it gets a try too
+        cipher
+            .decrypt(&self.nonce, data)
+            .map_err(|e| AppError::custom(format!("Decrypt error: {}", e)))
+    }
+}
+
+// the developer key is fixed-length, so the name after it is unambiguous. The on-chain
+// `data_hash` is the hash of this function's output, so it can't be bound in here.
+fn associated_data(developer: &Pubkey, game_name: &str) -> Vec<u8> {
+    [developer.as_ref(), game_name.as_bytes()].concat()
+}
+
+impl TryFrom<CreateGameResponse> for Encryptor {
+    type Error = AppError;
+
+    fn try_from(dto: CreateGameResponse) -> Result<Self, Self::Error> {
+        Encryptor::from_base64(&dto.encryption_key, &dto.nonce)
+    }
+}
+
+impl TryFrom<&KeyResponse> for Encryptor {
+    type Error = AppError;
+
+    fn try_from(dto: &KeyResponse) -> Result<Self, Self::Error> {
+        Encryptor::from_base64(&dto.encryption_key, &dto.nonce)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROM: &[u8] = &[0x00, 0xE0, 0xA2, 0x2A, 0x60, 0x0C, 0xD0, 0x1F];
+    const DEVELOPER: Pubkey = Pubkey::new_from_array([1; 32]);
+    const OTHER_DEVELOPER: Pubkey = Pubkey::new_from_array([2; 32]);
+
+    fn encryptor() -> Encryptor {
+        Encryptor::from_base64(&STANDARD.encode([7; 32]), &STANDARD.encode([9; 12])).unwrap()
+    }
+
+    #[test]
+    fn round_trips() {
+        let sealed = encryptor().encrypt(&DEVELOPER, "pong", ROM).unwrap();
+        assert_eq!(sealed[0], ENVELOPE_VERSION);
+        assert_eq!(sealed.len(), 1 + NONCE_LEN + ROM.len() + TAG_LEN);
+        assert_eq!(
+            encryptor().decrypt(&DEVELOPER, "pong", &sealed).unwrap(),
+            ROM
+        );
+    }
+
+    #[test]
+    fn opens_raw_ciphertexts_from_before_envelopes() {
+        let encryptor = encryptor();
+        let raw = Aes256Gcm::new(&encryptor.encryption_key)
+            .encrypt(&encryptor.nonce, ROM)
+            .unwrap();
+        assert_eq!(encryptor.decrypt(&DEVELOPER, "pong", &raw).unwrap(), ROM);
+    }
+
+    #[test]
+    fn only_opens_for_the_game_it_was_sealed_for() {
+        let sealed = encryptor().encrypt(&DEVELOPER, "pong", ROM).unwrap();
+        assert!(encryptor().decrypt(&DEVELOPER, "pong2", &sealed).is_err());
+        assert!(encryptor()
+            .decrypt(&OTHER_DEVELOPER, "pong", &sealed)
+            .is_err());
+    }
 }
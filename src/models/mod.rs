@@ -1,3 +1,5 @@
 mod encryptor;
+mod offchain_metadata;
 
 pub use encryptor::*;
+pub use offchain_metadata::*;
@@ -0,0 +1,26 @@
+use crate::vm::MachineProfile;
+use serde::{Deserialize, Serialize};
+
+/// JSON document the on-chain `game_uri` points to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OffchainGameMetadata {
+    pub name: String,
+    pub image: String,
+    #[serde(default)]
+    pub machine: MachineProfile,
+    /// Unencrypted [`crate::vm::Demo`] played on the store card, if the developer made one
+    #[serde(default)]
+    pub demo: Option<String>,
+}
+
+impl OffchainGameMetadata {
+    /// Games published before the metadata document existed point `game_uri` straight at the cover image
+    pub fn legacy(image: String) -> Self {
+        Self {
+            name: String::new(),
+            image,
+            machine: MachineProfile::default(),
+            demo: None,
+        }
+    }
+}
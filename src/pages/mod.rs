@@ -4,6 +4,7 @@ mod home;
 mod how_it_works;
 mod play_game;
 mod publish_game;
+mod rom_sandbox;
 
 pub use admin::*;
 pub use buy_game::*;
@@ -11,3 +12,4 @@ pub use home::*;
 pub use how_it_works::*;
 pub use play_game::*;
 pub use publish_game::*;
+pub use rom_sandbox::*;
@@ -0,0 +1,49 @@
+use crate::components::{GameScreen, LocalRom, MachineProfileForm};
+use crate::vm::MachineProfile;
+use leptos::{prelude::*, task::spawn_local};
+use thaw::{FileList, Upload, UploadDragger};
+use wasm_bindgen_futures::{js_sys::Uint8Array, JsFuture};
+
+/// Runs a ROM from disk with the debugger open; only served by development builds.
+#[component]
+pub fn RomSandboxPage() -> impl IntoView {
+    let profile = RwSignal::new(MachineProfile::default());
+    let local_rom = RwSignal::new(None::<LocalRom>);
+    let no_game = RwSignal::new(String::new());
+
+    let handle_rom = move |file_list: FileList| {
+        if let Some(file) = file_list.get(0) {
+            let file = file.to_owned();
+            spawn_local(async move {
+                let Ok(array_buffer) = JsFuture::from(file.array_buffer()).await else {
+                    leptos::logging::log!("Failed to read ROM file");
+                    return;
+                };
+                local_rom.set(Some(LocalRom {
+                    name: file.name(),
+                    bytes: Uint8Array::new(&array_buffer).to_vec(),
+                    profile: profile.get_untracked(),
+                }));
+            });
+        }
+    };
+
+    if !cfg!(debug_assertions) {
+        return view! { <p>"The ROM sandbox is only available in development builds."</p> }
+            .into_any();
+    }
+
+    view! {
+        <div class="play-page">
+            <h1>"ROM sandbox"</h1>
+            <GameScreen game_to_play=no_game local_rom=local_rom debug=true />
+            <div class="rom-sandbox">
+                <MachineProfileForm profile=profile />
+                <Upload custom_request=handle_rom>
+                    <UploadDragger>"Click or drag a ROM to run it"</UploadDragger>
+                </Upload>
+            </div>
+        </div>
+    }
+    .into_any()
+}
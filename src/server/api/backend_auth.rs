@@ -0,0 +1,13 @@
+use leptos::prelude::*;
+
+use crate::auth::BackendAuth;
+use crate::error::{AppError, AppResult};
+
+/// Whether calls that reach the backend need a [`crate::auth::GameGrant`] from the wallet
+#[server]
+pub async fn get_backend_auth() -> AppResult<BackendAuth> {
+    use crate::config::Config;
+
+    let config = use_context::<Config>().ok_or(AppError::custom("Can't get Config context"))?;
+    Ok(config.app.backend_auth)
+}
@@ -1,26 +1,37 @@
 use leptos::prelude::*;
 
+use crate::auth::GameGrant;
 use crate::error::{AppError, AppResult};
 use crate::server::{CreateGameRequest, CreateGameResponse};
 
+/// Registers a game under the wallet signed in to this session. The encryption key it
+/// returns passes through this server on its way to the browser. `grant` is only needed
+/// when the backend checks wallet signatures.
 #[server]
-pub async fn create_game(
-    name: String,
-    developer: String,
-    signature: String,
-    valid_period: i64,
-) -> AppResult<CreateGameResponse> {
-    use crate::server::ApiClient;
+pub async fn create_game(name: String, grant: Option<GameGrant>) -> AppResult<CreateGameResponse> {
+    use crate::auth::Purpose;
+    use crate::server::{require_session, ApiClient};
+    use solana_pubkey::Pubkey;
 
     let api_client =
         use_context::<ApiClient>().ok_or(AppError::custom("Can't get ApiClient context"))?;
+    let session = require_session(Purpose::Publish).await?;
+    let developer = session.public_key().to_string();
+    let developer_key: Pubkey = developer
+        .parse()
+        .map_err(|_| AppError::custom("Invalid developer key"))?;
 
     let request = CreateGameRequest {
+        credentials: api_client.credentials(
+            Purpose::Publish,
+            &name,
+            &developer_key,
+            &developer_key,
+            grant,
+        )?,
         name,
         player: developer.clone(),
         developer,
-        signature,
-        valid_period,
     };
 
     api_client.create_game(request).await
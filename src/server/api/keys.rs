@@ -1,38 +1,52 @@
 use leptos::prelude::*;
 
+use crate::auth::GameGrant;
 use crate::error::{AppError, AppResult};
 use crate::server::{GetKeyRequest, KeyResponse};
 
+/// Decryption key for a game, for the wallet signed in to this session. The key passes
+/// through this server on its way to the browser. `grant` is only needed when the backend
+/// checks wallet signatures.
 #[server]
 pub async fn get_key(
     name: String,
     developer: String,
-    player: String,
-    signature: String,
-    valid_period: i64,
+    grant: Option<GameGrant>,
 ) -> AppResult<KeyResponse> {
+    use crate::auth::Purpose;
     use crate::config::Config;
-    use crate::server::ApiClient;
+    use crate::server::{require_session, ApiClient};
     use solana_keypair::Signature;
     use solana_pubkey::Pubkey;
 
     let api_client =
         use_context::<ApiClient>().ok_or(AppError::custom("Can't get ApiClient context"))?;
     let config = use_context::<Config>().ok_or(AppError::custom("Can't get Config context"))?;
+    let session = require_session(Purpose::Play).await?;
+    let player = session.public_key().to_string();
+
+    let developer_key: Pubkey = developer
+        .parse()
+        .map_err(|_| AppError::custom("Invalid developer key"))?;
+    let player_key: Pubkey = player
+        .parse()
+        .map_err(|_| AppError::custom("Invalid player key"))?;
 
     let request = GetKeyRequest {
+        credentials: api_client.credentials(
+            Purpose::Play,
+            &name,
+            &developer_key,
+            &player_key,
+            grant,
+        )?,
         name: name.clone(),
-        developer: developer.clone(),
-        player: player.clone(),
-        signature,
-        valid_period,
+        developer,
+        player,
     };
 
     let response = api_client.get_key(request).await?;
 
-    let developer_key = Pubkey::from_str_const(&developer);
-    let player_key = Pubkey::from_str_const(&player);
-
     let payload = [
         &response.valid_period.to_le_bytes()[..],
         name.as_bytes(),
@@ -1,10 +1,10 @@
-mod encrypt;
+mod backend_auth;
 mod game;
 mod keys;
 mod request;
 mod response;
 
-pub use encrypt::*;
+pub use backend_auth::*;
 pub use game::*;
 pub use keys::*;
 pub use request::*;
@@ -14,21 +14,54 @@ use crate::error::{AppError, AppResult};
 
 #[cfg(feature = "ssr")]
 use reqwest::Client;
+#[cfg(feature = "ssr")]
+use solana_keypair::Keypair;
+#[cfg(feature = "ssr")]
+use std::sync::Arc;
 
 #[cfg(feature = "ssr")]
 #[derive(Clone)]
 pub struct ApiClient {
     client: Client,
     backend_addr: String,
+    /// Set when requests are signed by this server rather than the wallet
+    keypair: Option<Arc<Keypair>>,
 }
 
 #[cfg(feature = "ssr")]
 impl ApiClient {
-    pub fn new(client: Client, backend_addr: String) -> ApiClient {
+    pub fn new(client: Client, backend_addr: String, keypair: Option<Keypair>) -> ApiClient {
         Self {
             client,
             backend_addr,
+            keypair: keypair.map(Arc::new),
+        }
+    }
+
+    /// Proof for a request letting `player` act on the game: this server's signature,
+    /// good once within [`REQUEST_SECS`], or else the wallet's `grant` for the game
+    pub fn credentials(
+        &self,
+        purpose: crate::auth::Purpose,
+        name: &str,
+        developer: &solana_pubkey::Pubkey,
+        player: &solana_pubkey::Pubkey,
+        grant: Option<crate::auth::GameGrant>,
+    ) -> AppResult<Credentials> {
+        use crate::server::unix_now;
+
+        if let Some(keypair) = &self.keypair {
+            let auth = RequestAuth::sign(keypair, purpose, name, developer, player, unix_now());
+            return Ok(Credentials::Server { auth });
+        }
+        let grant = grant.ok_or(AppError::custom("Missing the wallet's grant for this game"))?;
+        if grant.valid_period <= unix_now() {
+            return Err(AppError::custom("The wallet's grant for this game expired"));
         }
+        Ok(Credentials::Wallet {
+            signature: grant.signature,
+            valid_period: grant.valid_period,
+        })
     }
 
     pub async fn create_game(&self, request: CreateGameRequest) -> AppResult<CreateGameResponse> {
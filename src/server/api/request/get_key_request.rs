@@ -1,3 +1,4 @@
+use super::Credentials;
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -5,6 +6,6 @@ pub struct GetKeyRequest {
     pub name: String,
     pub developer: String,
     pub player: String,
-    pub signature: String,
-    pub valid_period: i64,
+    #[serde(flatten)]
+    pub credentials: Credentials,
 }
@@ -1,5 +1,7 @@
 mod create_game_request;
 mod get_key_request;
+mod request_auth;
 
 pub use create_game_request::*;
 pub use get_key_request::*;
+pub use request_auth::*;
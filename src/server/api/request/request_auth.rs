@@ -0,0 +1,79 @@
+use crate::auth::Purpose;
+use serde::Serialize;
+
+/// How long the backend accepts a request after this server signs it
+#[cfg(feature = "ssr")]
+pub const REQUEST_SECS: i64 = 60;
+
+/// What a backend request carries to prove the wallet behind it
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum Credentials {
+    /// The wallet's [`crate::auth::GameGrant`], forwarded as is
+    Wallet {
+        signature: String,
+        valid_period: i64,
+    },
+    Server {
+        auth: RequestAuth,
+    },
+}
+
+/// This server vouching for the wallet signed in to the session. Signed with
+/// `app.backend_keypair`, which the backend knows the public key of, so the wallet's own
+/// sign-in signature never leaves this server.
+#[derive(Serialize)]
+pub struct RequestAuth {
+    pub purpose: Purpose,
+    /// Base58 of 32 random bytes; the backend refuses a nonce it has seen before
+    pub nonce: String,
+    /// Unix seconds, after which the backend refuses the request
+    pub expires_at: i64,
+    /// Base58 ed25519 signature over [`RequestAuth::payload`]
+    pub signature: String,
+}
+
+#[cfg(feature = "ssr")]
+impl RequestAuth {
+    pub fn sign(
+        keypair: &solana_keypair::Keypair,
+        purpose: Purpose,
+        name: &str,
+        developer: &solana_pubkey::Pubkey,
+        player: &solana_pubkey::Pubkey,
+        now: i64,
+    ) -> RequestAuth {
+        use solana_signer::Signer;
+
+        let nonce = rand::random::<[u8; 32]>();
+        let expires_at = now + REQUEST_SECS;
+        let payload = RequestAuth::payload(purpose, &nonce, expires_at, name, developer, player);
+        RequestAuth {
+            purpose,
+            nonce: bs58::encode(nonce).into_string(),
+            expires_at,
+            signature: keypair.sign_message(&payload).to_string(),
+        }
+    }
+
+    /// Nonce, `expires_at` (little-endian), developer, player, purpose byte and name. The
+    /// name is the only variable-length field, so it goes last.
+    pub fn payload(
+        purpose: Purpose,
+        nonce: &[u8; 32],
+        expires_at: i64,
+        name: &str,
+        developer: &solana_pubkey::Pubkey,
+        player: &solana_pubkey::Pubkey,
+    ) -> Vec<u8> {
+        [
+            &nonce[..],
+            &expires_at.to_le_bytes()[..],
+            developer.as_ref(),
+            player.as_ref(),
+            &[purpose as u8],
+            name.as_bytes(),
+        ]
+        .concat()
+    }
+}
@@ -0,0 +1,7 @@
+#[cfg(feature = "ssr")]
+mod session;
+mod sign_in;
+
+#[cfg(feature = "ssr")]
+pub use session::*;
+pub use sign_in::*;
@@ -0,0 +1,310 @@
+use crate::auth::{Purpose, SignInMessage};
+use crate::error::{AppError, AppResult};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+pub const SESSION_COOKIE: &str = "replayer_session";
+
+/// How long an issued sign-in message waits for its signature
+pub const CHALLENGE_SECS: i64 = 5 * 60;
+/// Sign-in messages one client can have waiting, since anyone can ask for them
+pub const MAX_CHALLENGES_PER_IP: usize = 32;
+/// Sign-in messages one wallet can have waiting; asking for another drops the oldest
+pub const MAX_CHALLENGES_PER_WALLET: usize = 4;
+
+/// A sign-in message the wallet signed, the proof it may act for its purpose. The
+/// signature stays out of it, since nothing past sign-in needs it.
+#[derive(Clone)]
+pub struct SignedIn {
+    pub message: SignInMessage,
+}
+
+impl SignedIn {
+    pub fn public_key(&self) -> &str {
+        &self.message.address
+    }
+}
+
+/// A sign-in message waiting for its signature, and the client that asked for it
+struct Challenge {
+    message: SignInMessage,
+    ip: Option<IpAddr>,
+}
+
+/// One wallet's sign-ins, at most one per purpose
+#[derive(Default)]
+pub struct Session {
+    signed_in: HashMap<Purpose, SignedIn>,
+}
+
+impl Session {
+    fn is_for(&self, address: &str) -> bool {
+        self.signed_in
+            .values()
+            .all(|signed_in| signed_in.public_key() == address)
+    }
+}
+
+/// Sign-in messages waiting for a signature, keyed by nonce, and live sessions, keyed by
+/// the id in their cookie, at most one per wallet. Kept in memory, so a restart signs
+/// everyone out.
+#[derive(Default)]
+pub struct SessionStore {
+    challenges: Mutex<HashMap<String, Challenge>>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl SessionStore {
+    /// Holds `message` until it's redeemed, for the client at `ip`. A client past
+    /// [`MAX_CHALLENGES_PER_IP`] is refused, so it can't crowd out anyone else's.
+    pub fn issue(&self, message: SignInMessage, ip: Option<IpAddr>, now: i64) -> AppResult<()> {
+        let mut challenges = self.challenges.lock().unwrap();
+        challenges.retain(|_, challenge| is_pending(&challenge.message, now));
+        if challenges
+            .values()
+            .filter(|challenge| challenge.ip == ip)
+            .count()
+            >= MAX_CHALLENGES_PER_IP
+        {
+            return Err(AppError::custom(
+                "Too many pending sign-ins, try again shortly",
+            ));
+        }
+
+        let mut for_wallet: Vec<_> = challenges
+            .iter()
+            .filter(|(_, challenge)| challenge.message.address == message.address)
+            .map(|(nonce, challenge)| (challenge.message.issued_at, nonce.clone()))
+            .collect();
+        if for_wallet.len() >= MAX_CHALLENGES_PER_WALLET {
+            for_wallet.sort();
+            for (_, nonce) in &for_wallet[..=for_wallet.len() - MAX_CHALLENGES_PER_WALLET] {
+                challenges.remove(nonce);
+            }
+        }
+
+        challenges.insert(message.nonce.clone(), Challenge { message, ip });
+        Ok(())
+    }
+
+    /// Each nonce can be redeemed once, within [`CHALLENGE_SECS`] of being issued, so a
+    /// signed message can't be replayed
+    pub fn redeem(&self, nonce: &str, now: i64) -> Option<SignInMessage> {
+        self.challenges
+            .lock()
+            .unwrap()
+            .remove(nonce)
+            .map(|challenge| challenge.message)
+            .filter(|message| is_pending(message, now))
+    }
+
+    /// Adds the sign-in to the session `id` if it's for the same wallet, otherwise starts a
+    /// new one, ending any other session the wallet had. Returns the session's id.
+    pub fn sign_in(&self, id: Option<&str>, signed_in: SignedIn, now: i64) -> String {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, session| {
+            session
+                .signed_in
+                .retain(|_, signed_in| !signed_in.message.is_expired(now));
+            !session.signed_in.is_empty()
+        });
+
+        let id = match id {
+            Some(id)
+                if sessions
+                    .get(id)
+                    .is_some_and(|session| session.is_for(signed_in.public_key())) =>
+            {
+                id.to_string()
+            }
+            _ => new_token(),
+        };
+        sessions.retain(|other, session| *other == id || !session.is_for(signed_in.public_key()));
+        sessions
+            .entry(id.clone())
+            .or_default()
+            .signed_in
+            .insert(signed_in.message.purpose, signed_in);
+        id
+    }
+
+    pub fn get(&self, id: &str, purpose: Purpose, now: i64) -> Option<SignedIn> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .get(id)?
+            .signed_in
+            .get(&purpose)
+            .filter(|signed_in| !signed_in.message.is_expired(now))
+            .cloned()
+    }
+
+    pub fn end(&self, id: &str) {
+        self.sessions.lock().unwrap().remove(id);
+    }
+}
+
+fn is_pending(challenge: &SignInMessage, now: i64) -> bool {
+    now < challenge.issued_at + CHALLENGE_SECS
+}
+
+/// Random base58 string, for nonces and session ids
+pub fn new_token() -> String {
+    bs58::encode(rand::random::<[u8; 32]>()).into_string()
+}
+
+pub fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// `Set-Cookie` value for a session, or one that clears it when `id` is `None`
+pub fn session_cookie(id: Option<&str>, max_age: i64) -> String {
+    format!(
+        "{SESSION_COOKIE}={}; Max-Age={}; Path=/; HttpOnly; Secure; SameSite=Strict",
+        id.unwrap_or_default(),
+        if id.is_some() { max_age } else { 0 }
+    )
+}
+
+/// Id of the session cookie sent with the current server function call
+pub async fn session_id() -> AppResult<Option<String>> {
+    use axum::http::{header, HeaderMap};
+
+    let headers: HeaderMap = leptos_axum::extract()
+        .await
+        .map_err(|e| AppError::custom(e.to_string()))?;
+    let id = headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, id)| id.to_string());
+    Ok(id)
+}
+
+/// Address of the client making the current server function call, when the server was
+/// started with connect info. Behind a proxy this is the proxy's address.
+pub async fn client_ip() -> Option<IpAddr> {
+    use axum::extract::ConnectInfo;
+    use std::net::SocketAddr;
+
+    leptos_axum::extract::<ConnectInfo<SocketAddr>>()
+        .await
+        .ok()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+/// The wallet behind the current server function call, signed in for `purpose`
+pub async fn require_session(purpose: Purpose) -> AppResult<SignedIn> {
+    use leptos::prelude::use_context;
+    use std::sync::Arc;
+
+    let store = use_context::<Arc<SessionStore>>()
+        .ok_or(AppError::custom("Can't get SessionStore context"))?;
+    session_id()
+        .await?
+        .and_then(|id| store.get(&id, purpose, unix_now()))
+        .ok_or(AppError::custom(format!(
+            "Not signed in to {}",
+            purpose.as_str()
+        )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT: Option<IpAddr> = Some(IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
+    const OTHER_CLIENT: Option<IpAddr> = Some(IpAddr::V4(std::net::Ipv4Addr::BROADCAST));
+
+    fn message(address: &str, purpose: Purpose, nonce: &str, now: i64) -> SignInMessage {
+        SignInMessage::new(
+            "replayer.test".to_string(),
+            address.to_string(),
+            purpose,
+            "devnet".to_string(),
+            nonce.to_string(),
+            now,
+        )
+    }
+
+    fn challenge(nonce: &str, now: i64) -> SignInMessage {
+        message("wallet", Purpose::Play, nonce, now)
+    }
+
+    #[test]
+    fn redeems_each_challenge_once() {
+        let store = SessionStore::default();
+        store.issue(challenge("a", 0), CLIENT, 0).unwrap();
+        assert_eq!(store.redeem("a", 1), Some(challenge("a", 0)));
+        assert_eq!(store.redeem("a", 1), None);
+        assert_eq!(store.redeem("b", 1), None);
+    }
+
+    #[test]
+    fn challenges_expire_long_before_sessions() {
+        let store = SessionStore::default();
+        store.issue(challenge("a", 0), CLIENT, 0).unwrap();
+        store.issue(challenge("b", 0), CLIENT, 0).unwrap();
+        assert!(store.redeem("a", CHALLENGE_SECS - 1).is_some());
+        assert_eq!(store.redeem("b", CHALLENGE_SECS), None);
+    }
+
+    #[test]
+    fn caps_pending_challenges_per_client() {
+        let store = SessionStore::default();
+        for nonce in 0..MAX_CHALLENGES_PER_IP {
+            let wallet = format!("wallet{nonce}");
+            let message = message(&wallet, Purpose::Play, &nonce.to_string(), 0);
+            store.issue(message, CLIENT, 0).unwrap();
+        }
+        assert!(store.issue(challenge("full", 0), CLIENT, 0).is_err());
+        // other clients aren't held up
+        assert!(store.issue(challenge("other", 0), OTHER_CLIENT, 0).is_ok());
+        // and expired ones make room again
+        assert!(store
+            .issue(challenge("later", CHALLENGE_SECS), CLIENT, CHALLENGE_SECS)
+            .is_ok());
+    }
+
+    #[test]
+    fn drops_a_wallets_oldest_challenge() {
+        let store = SessionStore::default();
+        for now in 0..=MAX_CHALLENGES_PER_WALLET as i64 {
+            store
+                .issue(challenge(&now.to_string(), now), CLIENT, now)
+                .unwrap();
+        }
+        assert_eq!(store.redeem("0", 10), None);
+        assert!(store.redeem("1", 10).is_some());
+        assert!(store
+            .redeem(&MAX_CHALLENGES_PER_WALLET.to_string(), 10)
+            .is_some());
+    }
+
+    #[test]
+    fn keeps_one_session_per_wallet() {
+        let store = SessionStore::default();
+        let signed_in = |address, purpose| SignedIn {
+            message: message(address, purpose, "n", 0),
+        };
+        let first = store.sign_in(None, signed_in("a", Purpose::Play), 0);
+        let same = store.sign_in(Some(&first), signed_in("a", Purpose::Publish), 0);
+        assert_eq!(first, same);
+
+        // signing in again elsewhere ends the first browser's session
+        let second = store.sign_in(None, signed_in("a", Purpose::Play), 0);
+        assert_ne!(first, second);
+        assert!(store.get(&first, Purpose::Play, 0).is_none());
+        assert!(store.get(&second, Purpose::Play, 0).is_some());
+
+        let other = store.sign_in(None, signed_in("b", Purpose::Play), 0);
+        assert!(store.get(&second, Purpose::Play, 0).is_some());
+        assert!(store.get(&other, Purpose::Play, 0).is_some());
+    }
+}
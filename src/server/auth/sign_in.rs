@@ -0,0 +1,100 @@
+use leptos::prelude::*;
+
+use crate::auth::{Purpose, SignInMessage};
+use crate::error::{AppError, AppResult};
+
+/// Issues a sign-in message for `public_key` to sign, bound to this site and cluster
+#[server]
+pub async fn get_sign_in_message(public_key: String, purpose: Purpose) -> AppResult<SignInMessage> {
+    use crate::config::Config;
+    use crate::server::{client_ip, new_token, unix_now, SessionStore};
+    use solana_pubkey::Pubkey;
+    use std::sync::Arc;
+
+    let config = use_context::<Config>().ok_or(AppError::custom("Can't get Config context"))?;
+    let store = use_context::<Arc<SessionStore>>()
+        .ok_or(AppError::custom("Can't get SessionStore context"))?;
+
+    public_key
+        .parse::<Pubkey>()
+        .map_err(|_| AppError::custom("Invalid wallet address"))?;
+
+    let now = unix_now();
+    let message = SignInMessage::new(
+        config.app.domain.clone(),
+        public_key,
+        purpose,
+        config.solana.cluster.clone(),
+        new_token(),
+        now,
+    );
+    store.issue(message.clone(), client_ip().await, now)?;
+
+    Ok(message)
+}
+
+/// Exchanges a signed sign-in message for an HTTP-only session cookie, returning the wallet
+#[server]
+pub async fn sign_in(message: SignInMessage, signature: String) -> AppResult<String> {
+    use crate::server::{session_cookie, session_id, unix_now, SessionStore, SignedIn};
+    use axum::http::{header, HeaderValue};
+    use leptos_axum::ResponseOptions;
+    use std::sync::Arc;
+
+    let store = use_context::<Arc<SessionStore>>()
+        .ok_or(AppError::custom("Can't get SessionStore context"))?;
+    let response =
+        use_context::<ResponseOptions>().ok_or(AppError::custom("Can't get response options"))?;
+
+    let now = unix_now();
+    let issued = store.redeem(&message.nonce, now).ok_or(AppError::custom(
+        "Unknown, expired or already used sign-in nonce",
+    ))?;
+    message.verify(&issued, &signature, now)?;
+
+    let max_age = message.expiration_time - now;
+    let public_key = message.address.clone();
+    let id = store.sign_in(session_id().await?.as_deref(), SignedIn { message }, now);
+    let cookie = HeaderValue::from_str(&session_cookie(Some(&id), max_age))
+        .map_err(|e| AppError::custom(e.to_string()))?;
+    response.insert_header(header::SET_COOKIE, cookie);
+
+    Ok(public_key)
+}
+
+#[server]
+pub async fn sign_out() -> AppResult<()> {
+    use crate::server::{session_cookie, session_id, SessionStore};
+    use axum::http::{header, HeaderValue};
+    use leptos_axum::ResponseOptions;
+    use std::sync::Arc;
+
+    let store = use_context::<Arc<SessionStore>>()
+        .ok_or(AppError::custom("Can't get SessionStore context"))?;
+    let response =
+        use_context::<ResponseOptions>().ok_or(AppError::custom("Can't get response options"))?;
+
+    if let Some(id) = session_id().await? {
+        store.end(&id);
+    }
+    let cookie = HeaderValue::from_str(&session_cookie(None, 0))
+        .map_err(|e| AppError::custom(e.to_string()))?;
+    response.insert_header(header::SET_COOKIE, cookie);
+
+    Ok(())
+}
+
+/// The wallet this browser is signed in with for `purpose`, if any
+#[server]
+pub async fn get_session(purpose: Purpose) -> AppResult<Option<String>> {
+    use crate::server::{session_id, unix_now, SessionStore};
+    use std::sync::Arc;
+
+    let store = use_context::<Arc<SessionStore>>()
+        .ok_or(AppError::custom("Can't get SessionStore context"))?;
+
+    Ok(session_id()
+        .await?
+        .and_then(|id| store.get(&id, purpose, unix_now()))
+        .map(|signed_in| signed_in.public_key().to_string()))
+}
@@ -1,9 +1,11 @@
 mod api;
+mod auth;
 mod nft;
 mod query;
 mod transaction_builder;
 
 pub use api::*;
+pub use auth::*;
 pub use nft::*;
 pub use query::*;
 pub use transaction_builder::*;
@@ -0,0 +1,164 @@
+use crate::error::AppResult;
+use crate::models::OffchainGameMetadata;
+use crate::vm::MachineProfile;
+use leptos::prelude::*;
+
+// metadata documents are a few hundred bytes, this leaves room for long names
+#[cfg(feature = "ssr")]
+const MAX_METADATA_BYTES: usize = 64 * 1024;
+
+/// Bundlr uploads are served from its own gateway and from Arweave's
+#[cfg(feature = "ssr")]
+const ARWEAVE_GATEWAY_HOST: &str = "arweave.net";
+
+/// The game's on-chain metadata account, which says where its off-chain documents live
+#[cfg(feature = "ssr")]
+fn fetch_game_account(
+    developer: &str,
+    game_name: &str,
+) -> AppResult<crate::accounts::GameMetadata> {
+    use crate::accounts::fetch_game_metadata;
+    use crate::error::AppError;
+    use crate::utils::GAME_METADATA_SEED;
+    use solana_client::rpc_client::RpcClient;
+    use solana_pubkey::Pubkey;
+    use std::sync::Arc;
+
+    let solana_client =
+        use_context::<Arc<RpcClient>>().ok_or(AppError::custom("Can't get context"))?;
+    let developer_key: Pubkey = developer
+        .parse()
+        .map_err(|_| AppError::custom("Invalid developer key"))?;
+
+    let (game_metadata_pda, _) = Pubkey::find_program_address(
+        &[
+            GAME_METADATA_SEED,
+            developer_key.as_ref(),
+            game_name.as_bytes(),
+        ],
+        &crate::REPLAYER_ID,
+    );
+
+    Ok(fetch_game_metadata(&solana_client, &game_metadata_pda)?.data)
+}
+
+/// Requests `uri` from the upload gateway. Other hosts and redirects to them are refused,
+/// since the URIs come from accounts any developer can write.
+#[cfg(feature = "ssr")]
+async fn gateway_get(uri: &str) -> AppResult<reqwest::Response> {
+    use crate::config::Config;
+    use crate::error::AppError;
+    use reqwest::{redirect::Policy, Client, Url};
+
+    let config = use_context::<Config>().ok_or(AppError::custom("Can't get Config context"))?;
+    let bundlr_host = Url::parse(&config.solana.bundlr_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .ok_or(AppError::custom("Invalid bundlr url"))?;
+    let allowed = move |url: &Url| {
+        url.scheme() == "https"
+            && url
+                .host_str()
+                .is_some_and(|host| host == bundlr_host || host == ARWEAVE_GATEWAY_HOST)
+    };
+
+    let url = Url::parse(uri).map_err(|e| AppError::custom(format!("Invalid uri: {e}")))?;
+    if !allowed(&url) {
+        return Err(AppError::custom(format!(
+            "Not an upload gateway uri: {uri}"
+        )));
+    }
+    let client = Client::builder()
+        .redirect(Policy::custom(move |attempt| {
+            if attempt.previous().len() < 5 && allowed(attempt.url()) {
+                attempt.follow()
+            } else {
+                attempt.stop()
+            }
+        }))
+        .build()
+        .map_err(|e| AppError::custom(format!("Client error: {e}")))?;
+
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| AppError::custom(format!("Request failed: {e}")))?;
+    if !response.status().is_success() {
+        return Err(AppError::custom(format!(
+            "Can't fetch {uri}: {}",
+            response.status()
+        )));
+    }
+    Ok(response)
+}
+
+/// Reads a response body, giving up once it passes `max_len` bytes
+#[cfg(feature = "ssr")]
+async fn read_limited(mut response: reqwest::Response, max_len: usize) -> AppResult<Vec<u8>> {
+    use crate::error::AppError;
+
+    let too_large = || AppError::custom(format!("Response is larger than {max_len} bytes"));
+    if response
+        .content_length()
+        .is_some_and(|len| len > max_len as u64)
+    {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| AppError::custom(format!("Read error: {e}")))?
+    {
+        if body.len() + chunk.len() > max_len {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+#[server]
+pub async fn get_offchain_game_metadata(
+    developer: String,
+    game_name: String,
+) -> AppResult<OffchainGameMetadata> {
+    use crate::error::AppError;
+
+    let game_uri = fetch_game_account(&developer, &game_name)?.game_uri;
+    let response = gateway_get(&game_uri).await?;
+
+    let is_json = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !is_json {
+        return Ok(OffchainGameMetadata::legacy(game_uri));
+    }
+
+    let body = read_limited(response, MAX_METADATA_BYTES).await?;
+    serde_json::from_slice::<OffchainGameMetadata>(&body)
+        .map_err(|e| AppError::custom(format!("Parse error: {e}")))
+}
+
+#[server]
+pub async fn get_game_profile(developer: String, game_name: String) -> AppResult<MachineProfile> {
+    let metadata = get_offchain_game_metadata(developer, game_name).await?;
+
+    Ok(metadata.machine)
+}
+
+#[server]
+pub async fn get_game_demo(developer: String, game_name: String) -> AppResult<Vec<u8>> {
+    use crate::error::AppError;
+    use crate::vm::MAX_DEMO_BYTES;
+
+    let metadata = get_offchain_game_metadata(developer, game_name).await?;
+    let demo_uri = metadata.demo.ok_or(AppError::custom("Game has no demo"))?;
+    let response = gateway_get(&demo_uri).await?;
+
+    read_limited(response, MAX_DEMO_BYTES).await
+}
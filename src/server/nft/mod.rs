@@ -1,3 +1,5 @@
+mod game_profile;
 mod upload_metadata;
 
+pub use game_profile::*;
 pub use upload_metadata::*;
@@ -1,3 +1,4 @@
+use crate::vm::MachineProfile;
 use leptos::prelude::*;
 
 #[cfg(feature = "ssr")]
@@ -28,18 +29,55 @@ fn bundlr_upload(
     })
 }
 
+/// Uploads the cover image, demo and metadata document of a game the wallet signed in to
+/// this session publishes. Returns the metadata document's URI.
 #[server]
 pub async fn upload_game_metadata(
+    game_name: String,
     image_b58: String,
     content_type: String,
+    machine: MachineProfile,
+    demo_b58: Option<String>,
 ) -> Result<String, ServerFnError> {
+    use crate::auth::Purpose;
     use crate::config::Config;
+    use crate::models::OffchainGameMetadata;
+    use crate::server::require_session;
+    use crate::vm::{Demo, MAX_DEMO_BYTES};
     use bundlr_sdk::{currency::solana::Solana, tags::Tag, Bundlr};
     use std::sync::Arc;
 
+    // uploads are paid for by this server
+    require_session(Purpose::Publish)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    machine
+        .validate()
+        .map_err(|e| ServerFnError::new(format!("Invalid machine profile: {e}")))?;
+
     let image_bytes = bs58::decode(&image_b58)
         .into_vec()
         .map_err(|e| ServerFnError::new(format!("Decode image: {e}")))?;
+    // base58 takes under 1.38 characters a byte, so longer strings can't decode small enough
+    let too_large = || ServerFnError::new(format!("Demo is larger than {MAX_DEMO_BYTES} bytes"));
+    if demo_b58
+        .as_ref()
+        .is_some_and(|demo| demo.len() > MAX_DEMO_BYTES * 138 / 100)
+    {
+        return Err(too_large());
+    }
+    let demo_bytes = demo_b58
+        .map(|demo| bs58::decode(&demo).into_vec())
+        .transpose()
+        .map_err(|e| ServerFnError::new(format!("Decode demo: {e}")))?;
+    if let Some(demo_bytes) = &demo_bytes {
+        if demo_bytes.len() > MAX_DEMO_BYTES {
+            return Err(too_large());
+        }
+        Demo::from_bytes(demo_bytes)
+            .map_err(|e| ServerFnError::new(format!("Invalid demo: {e}")))?;
+    }
 
     let bundlr = use_context::<Arc<Bundlr<Solana>>>()
         .ok_or_else(|| ServerFnError::new("Bundlr client not available"))?;
@@ -58,5 +96,39 @@ pub async fn upload_game_metadata(
     .map_err(|e| ServerFnError::new(format!("Task error: {e}")))?
     .map_err(ServerFnError::new)?;
 
-    Ok(image_uri)
+    // Upload the attract-mode demo, which is public like the cover image
+    let demo_uri = match demo_bytes {
+        Some(demo_bytes) => {
+            let demo_tags = vec![Tag::new("Content-Type", "application/octet-stream")];
+            let bundlr_clone = bundlr.clone();
+            let gw = gateway_url.clone();
+            let uri = tokio::task::spawn_blocking(move || {
+                bundlr_upload(bundlr_clone, demo_bytes, demo_tags, gw)
+            })
+            .await
+            .map_err(|e| ServerFnError::new(format!("Task error: {e}")))?
+            .map_err(ServerFnError::new)?;
+            Some(uri)
+        }
+        None => None,
+    };
+
+    // Upload the metadata document the game account will point to
+    let metadata = OffchainGameMetadata {
+        name: game_name,
+        image: image_uri,
+        machine,
+        demo: demo_uri,
+    };
+    let metadata_bytes = serde_json::to_vec(&metadata)
+        .map_err(|e| ServerFnError::new(format!("Encode metadata: {e}")))?;
+    let metadata_tags = vec![Tag::new("Content-Type", "application/json")];
+    let metadata_uri = tokio::task::spawn_blocking(move || {
+        bundlr_upload(bundlr, metadata_bytes, metadata_tags, gateway_url)
+    })
+    .await
+    .map_err(|e| ServerFnError::new(format!("Task error: {e}")))?
+    .map_err(ServerFnError::new)?;
+
+    Ok(metadata_uri)
 }
@@ -14,6 +14,50 @@ pub struct FetchedGameMetadata {
     pub data: GameMetadata,
 }
 
+/// Encrypted ROM bytes as stored on chain
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FetchedGameData {
+    pub data: Vec<u8>,
+    /// Unset while the developer is still uploading chunks
+    pub is_finalized: bool,
+}
+
+/// Why [`FetchedGameData::verify`] refused a game's data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameDataError {
+    NotFinalized,
+    HashMismatch,
+}
+
+impl std::fmt::Display for GameDataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameDataError::NotFinalized => write!(f, "This game hasn't finished uploading yet."),
+            GameDataError::HashMismatch => write!(
+                f,
+                "Integrity check failed: this game's data doesn't match the hash it was \
+                 published with."
+            ),
+        }
+    }
+}
+
+impl FetchedGameData {
+    /// Checks the upload is complete and matches the `data_hash` it was published with,
+    /// before anything tries to decrypt it
+    pub fn verify(&self, data_hash: &[u8; 32]) -> Result<(), GameDataError> {
+        use sha2::{Digest, Sha256};
+
+        if !self.is_finalized {
+            return Err(GameDataError::NotFinalized);
+        }
+        if Sha256::digest(&self.data).as_slice() != data_hash {
+            return Err(GameDataError::HashMismatch);
+        }
+        Ok(())
+    }
+}
+
 #[server]
 pub async fn get_all_games() -> AppResult<Vec<FetchedGameMetadata>> {
     use crate::{accounts::fetch_all_game_metadata, utils::GAME_METADATA_DISCRIMINATOR};
@@ -116,7 +160,35 @@ pub async fn get_owned_games(player: String) -> AppResult<Vec<FetchedGameMetadat
 }
 
 #[server]
-pub async fn get_game_data(developer: String, game_name: String) -> AppResult<Vec<u8>> {
+pub async fn get_game_metadata(
+    developer: String,
+    game_name: String,
+) -> AppResult<FetchedGameMetadata> {
+    use crate::{accounts::fetch_game_metadata, utils::GAME_METADATA_SEED};
+    use solana_client::rpc_client::RpcClient;
+    let solana_client =
+        use_context::<Arc<RpcClient>>().ok_or(AppError::custom("Can't get context"))?;
+    let developer_key = Pubkey::from_str_const(&developer);
+
+    let (game_metadata_pda, _) = Pubkey::find_program_address(
+        &[
+            GAME_METADATA_SEED,
+            developer_key.as_ref(),
+            game_name.as_bytes(),
+        ],
+        &crate::REPLAYER_ID,
+    );
+
+    let decoded_acc = fetch_game_metadata(&solana_client, &game_metadata_pda)?;
+
+    Ok(FetchedGameMetadata {
+        address: decoded_acc.address,
+        data: decoded_acc.data,
+    })
+}
+
+#[server]
+pub async fn get_game_data(developer: String, game_name: String) -> AppResult<FetchedGameData> {
     use crate::accounts::fetch_game_data;
     use solana_client::rpc_client::RpcClient;
     let solana_client =
@@ -132,5 +204,43 @@ pub async fn get_game_data(developer: String, game_name: String) -> AppResult<Ve
     let data_len = decoded_acc.data.length as usize;
     let rom_data = decoded_acc.data.data[..data_len].to_vec();
 
-    Ok(rom_data)
+    Ok(FetchedGameData {
+        data: rom_data,
+        is_finalized: decoded_acc.data.is_finalized != 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    fn game_data(is_finalized: bool) -> FetchedGameData {
+        FetchedGameData {
+            data: vec![0x12, 0x34, 0x56],
+            is_finalized,
+        }
+    }
+
+    #[test]
+    fn accepts_finalized_data_matching_its_hash() {
+        let data = game_data(true);
+        let hash: [u8; 32] = Sha256::digest(&data.data).into();
+        assert_eq!(data.verify(&hash), Ok(()));
+    }
+
+    #[test]
+    fn rejects_data_not_matching_its_hash() {
+        let data = game_data(true);
+        let mut hash: [u8; 32] = Sha256::digest(&data.data).into();
+        hash[0] ^= 1;
+        assert_eq!(data.verify(&hash), Err(GameDataError::HashMismatch));
+    }
+
+    #[test]
+    fn rejects_data_still_being_uploaded() {
+        let data = game_data(false);
+        let hash: [u8; 32] = Sha256::digest(&data.data).into();
+        assert_eq!(data.verify(&hash), Err(GameDataError::NotFinalized));
+    }
 }
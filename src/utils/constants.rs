@@ -15,6 +15,10 @@ pub const SIGN_AND_SEND_TRANSACTION_METHOD: &'static str = "signAndSendTransacti
 
 // Local Storage
 pub const LS_PUBLIC_KEY: &'static str = "PUBLIC_KEY";
+pub const LS_SAVE_STATE_PREFIX: &'static str = "SAVE_STATE";
+pub const LS_KEY_BINDINGS_PREFIX: &'static str = "KEY_BINDINGS";
+pub const LS_GAMEPAD_LAYOUT: &'static str = "GAMEPAD_LAYOUT";
+pub const LS_DISPLAY_OPTIONS: &'static str = "DISPLAY_OPTIONS";
 
 // Discriminators
 pub const GAME_METADATA_DISCRIMINATOR: &[u8] = &[4];
@@ -21,4 +21,13 @@ pub mod deserializer {
         let keypair = Keypair::from_base58_string(&s);
         Ok(keypair.to_bytes())
     }
+
+    pub fn deserialize_optional_keypair<'de, D>(
+        deserializer: D,
+    ) -> Result<Option<[u8; 64]>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_keypair(deserializer).map(Some)
+    }
 }
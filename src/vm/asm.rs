@@ -0,0 +1,798 @@
+use crate::vm::{STARTING_ADDRESS, XO_RAM_SIZE};
+use std::collections::{HashMap, VecDeque};
+use thiserror::Error;
+
+/// Why a program failed to assemble, with the source line it happened on.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("line {line}: {message}")]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+pub type AsmResult<T> = Result<T, AsmError>;
+
+/// Assembles the subset of Octo this project uses into a ROM loaded at `0x200`.
+///
+/// Supported: every instruction the disassembler prints, labels, `:call`, bare label calls,
+/// `:const`, `:alias`, `:macro`, `:byte`, raw numbers as sprite data, `if .. then`,
+/// `if .. begin .. else .. end` and `loop .. while .. again`. As in Octo, a `jump main` is
+/// placed at `0x200` unless the program starts with `: main`.
+pub fn assemble(source: &str) -> AsmResult<Vec<u8>> {
+    Assembler::new(source).run()
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    line: usize,
+    // macros this token came out of, innermost last
+    expanded_from: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FixupKind {
+    // low 12 bits of the opcode at `at`
+    Address,
+    // 16-bit operand of `i := long`
+    Long,
+}
+
+#[derive(Debug)]
+struct Fixup {
+    at: usize,
+    kind: FixupKind,
+    label: String,
+    line: usize,
+}
+
+#[derive(Debug)]
+struct Macro {
+    params: Vec<String>,
+    body: Vec<Token>,
+}
+
+// open `if .. begin` and `loop` blocks
+#[derive(Debug)]
+enum Block {
+    If { jump_at: usize },
+    Else { jump_at: usize },
+    Loop { start: u16, exits: Vec<usize> },
+}
+
+// skip conditions as written after `if`/`while`
+#[derive(Debug, Clone, Copy)]
+enum Condition {
+    EqualByte(u16, u16),
+    NotEqualByte(u16, u16),
+    Equal(u16, u16),
+    NotEqual(u16, u16),
+    Key(u16),
+    NotKey(u16),
+}
+
+impl Condition {
+    fn negate(self) -> Condition {
+        match self {
+            Condition::EqualByte(x, nn) => Condition::NotEqualByte(x, nn),
+            Condition::NotEqualByte(x, nn) => Condition::EqualByte(x, nn),
+            Condition::Equal(x, y) => Condition::NotEqual(x, y),
+            Condition::NotEqual(x, y) => Condition::Equal(x, y),
+            Condition::Key(x) => Condition::NotKey(x),
+            Condition::NotKey(x) => Condition::Key(x),
+        }
+    }
+
+    // the instruction that skips the next one when the condition is false
+    fn skip_unless(self) -> u16 {
+        match self {
+            Condition::EqualByte(x, nn) => 0x4000 | x << 8 | nn,
+            Condition::NotEqualByte(x, nn) => 0x3000 | x << 8 | nn,
+            Condition::Equal(x, y) => 0x9000 | x << 8 | y << 4,
+            Condition::NotEqual(x, y) => 0x5000 | x << 8 | y << 4,
+            Condition::Key(x) => 0xE0A1 | x << 8,
+            Condition::NotKey(x) => 0xE09E | x << 8,
+        }
+    }
+}
+
+struct Assembler {
+    tokens: VecDeque<Token>,
+    out: Vec<u8>,
+    labels: HashMap<String, u16>,
+    consts: HashMap<String, u16>,
+    aliases: HashMap<String, u16>,
+    macros: HashMap<String, Macro>,
+    fixups: Vec<Fixup>,
+    blocks: Vec<Block>,
+    line: usize,
+    expanded_from: Vec<String>,
+}
+
+impl Assembler {
+    fn new(source: &str) -> Self {
+        let tokens = source
+            .lines()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                let code = line.split('#').next().unwrap_or_default();
+                code.split_whitespace().map(move |text| Token {
+                    text: text.to_string(),
+                    line: i + 1,
+                    expanded_from: vec![],
+                })
+            })
+            .collect();
+        Assembler {
+            tokens,
+            out: vec![],
+            labels: HashMap::new(),
+            consts: HashMap::new(),
+            aliases: HashMap::new(),
+            macros: HashMap::new(),
+            fixups: vec![],
+            blocks: vec![],
+            line: 1,
+            expanded_from: vec![],
+        }
+    }
+
+    fn run(mut self) -> AsmResult<Vec<u8>> {
+        while let Some(token) = self.next_token() {
+            self.statement(&token.text)?;
+        }
+
+        if !self.blocks.is_empty() {
+            return Err(self.error("unclosed `begin` or `loop`"));
+        }
+        for fixup in std::mem::take(&mut self.fixups) {
+            let Some(&address) = self.labels.get(&fixup.label) else {
+                return Err(AsmError {
+                    line: fixup.line,
+                    message: format!("undefined label `{}`", fixup.label),
+                });
+            };
+            match fixup.kind {
+                FixupKind::Address => {
+                    if address > 0xFFF {
+                        return Err(AsmError {
+                            line: fixup.line,
+                            message: format!("`{}` is out of 12-bit range", fixup.label),
+                        });
+                    }
+                    self.out[fixup.at] |= (address >> 8) as u8;
+                    self.out[fixup.at + 1] = address as u8;
+                }
+                FixupKind::Long => {
+                    self.out[fixup.at..fixup.at + 2].copy_from_slice(&address.to_be_bytes());
+                }
+            }
+        }
+        Ok(self.out)
+    }
+
+    fn statement(&mut self, token: &str) -> AsmResult<()> {
+        match token {
+            ":" => {
+                let name = self.expect_name()?;
+                self.define_label(name)
+            }
+            ":const" => {
+                let name = self.expect_name()?;
+                let value = self.expect_value()?;
+                self.consts.insert(name, value);
+                Ok(())
+            }
+            ":alias" => {
+                let name = self.expect_name()?;
+                let register = self.expect_register()?;
+                self.aliases.insert(name, register);
+                Ok(())
+            }
+            ":macro" => self.define_macro(),
+            ":call" => self.address_op(0x2000),
+            ":byte" => {
+                let value = self.expect_value()?;
+                self.emit_byte(value)
+            }
+            "clear" => self.emit(0x00E0),
+            "return" => self.emit(0x00EE),
+            "scroll-down" => {
+                let n = self.expect_nibble()?;
+                self.emit(0x00C0 | n)
+            }
+            "scroll-up" => {
+                let n = self.expect_nibble()?;
+                self.emit(0x00D0 | n)
+            }
+            "scroll-right" => self.emit(0x00FB),
+            "scroll-left" => self.emit(0x00FC),
+            "exit" => self.emit(0x00FD),
+            "lores" => self.emit(0x00FE),
+            "hires" => self.emit(0x00FF),
+            "audio" => self.emit(0xF002),
+            "plane" => {
+                let n = self.expect_nibble()?;
+                self.emit(0xF001 | n << 8)
+            }
+            "jump" => self.address_op(0x1000),
+            "jump0" => self.address_op(0xB000),
+            "sprite" => {
+                let x = self.expect_register()?;
+                let y = self.expect_register()?;
+                let n = self.expect_nibble()?;
+                self.emit(0xD000 | x << 8 | y << 4 | n)
+            }
+            "bcd" => self.register_op(0xF033),
+            "saveflags" => self.register_op(0xF075),
+            "loadflags" => self.register_op(0xF085),
+            "save" | "load" => self.save_load(token == "save"),
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let x = self.expect_register()?;
+                let op = match token {
+                    "delay" => 0xF015,
+                    "buzzer" => 0xF018,
+                    _ => 0xF03A,
+                };
+                self.emit(op | x << 8)
+            }
+            "i" => self.i_op(),
+            "if" => self.if_statement(),
+            "else" => self.else_statement(),
+            "end" => self.end_statement(),
+            "loop" => {
+                self.ensure_entry()?;
+                self.blocks.push(Block::Loop {
+                    start: self.here()?,
+                    exits: vec![],
+                });
+                Ok(())
+            }
+            "while" => self.while_statement(),
+            "again" => self.again_statement(),
+            _ => {
+                if let Some(x) = self.register(token) {
+                    return self.register_statement(x);
+                }
+                if let Some(mac) = self.macros.get(token) {
+                    return self.expand_macro(token, mac.params.len());
+                }
+                if let Some(&address) = self.labels.get(token) {
+                    return self.emit_address(0x2000, address, token);
+                }
+                if let Some(value) = self.value(token) {
+                    return self.emit_byte(value);
+                }
+                if token.starts_with(':') {
+                    return Err(self.error(format!("unsupported directive `{token}`")));
+                }
+                // a bare name calls the subroutine, which may be defined later
+                self.address_op_to(0x2000, token)
+            }
+        }
+    }
+
+    fn define_label(&mut self, name: String) -> AsmResult<()> {
+        if self.labels.contains_key(&name) {
+            return Err(self.error(format!("label `{name}` is defined twice")));
+        }
+        if name != "main" {
+            self.ensure_entry()?;
+        }
+        let address = self.here()?;
+        self.labels.insert(name, address);
+        Ok(())
+    }
+
+    fn define_macro(&mut self) -> AsmResult<()> {
+        let name = self.expect_name()?;
+        let mut params = vec![];
+        loop {
+            let token = self.expect_token()?;
+            if token == "{" {
+                break;
+            }
+            params.push(token);
+        }
+
+        let mut body = vec![];
+        let mut depth = 1;
+        loop {
+            let token = self
+                .tokens
+                .pop_front()
+                .ok_or_else(|| self.error(format!("unterminated macro `{name}`")))?;
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+            body.push(token);
+        }
+        self.macros.insert(name, Macro { params, body });
+        Ok(())
+    }
+
+    fn expand_macro(&mut self, name: &str, arity: usize) -> AsmResult<()> {
+        if self.expanded_from.iter().any(|outer| outer == name) {
+            return Err(self.error(format!("macro `{name}` expands into itself")));
+        }
+        let mut expanded_from = self.expanded_from.clone();
+        expanded_from.push(name.to_string());
+
+        let mut args = HashMap::new();
+        for i in 0..arity {
+            let arg = self.expect_token()?;
+            args.insert(self.macros[name].params[i].clone(), arg);
+        }
+        let line = self.line;
+        let body: Vec<Token> = self.macros[name]
+            .body
+            .iter()
+            .map(|token| Token {
+                text: args.get(&token.text).unwrap_or(&token.text).clone(),
+                line,
+                expanded_from: expanded_from.clone(),
+            })
+            .collect();
+        for token in body.into_iter().rev() {
+            self.tokens.push_front(token);
+        }
+        Ok(())
+    }
+
+    fn register_statement(&mut self, x: u16) -> AsmResult<()> {
+        let operator = self.expect_token()?;
+        let operand = self.expect_token()?;
+        if let Some(y) = self.register(&operand) {
+            let n = match operator.as_str() {
+                ":=" => 0,
+                "|=" => 1,
+                "&=" => 2,
+                "^=" => 3,
+                "+=" => 4,
+                "-=" => 5,
+                ">>=" => 6,
+                "=-" => 7,
+                "<<=" => 0xE,
+                _ => return Err(self.error(format!("unknown operator `{operator}`"))),
+            };
+            return self.emit(0x8000 | x << 8 | y << 4 | n);
+        }
+
+        match (operator.as_str(), operand.as_str()) {
+            (":=", "delay") => self.emit(0xF007 | x << 8),
+            (":=", "key") => self.emit(0xF00A | x << 8),
+            (":=", "random") => {
+                let mask = self.expect_byte()?;
+                self.emit(0xC000 | x << 8 | mask)
+            }
+            (":=", _) => {
+                let nn = self.byte(&operand)?;
+                self.emit(0x6000 | x << 8 | nn)
+            }
+            ("+=", _) => {
+                let nn = self.byte(&operand)?;
+                self.emit(0x7000 | x << 8 | nn)
+            }
+            ("-=", _) => {
+                let nn = self.byte(&operand)?;
+                self.emit(0x7000 | x << 8 | (0x100 - nn) & 0xFF)
+            }
+            _ => Err(self.error(format!("can't assemble `{operator} {operand}`"))),
+        }
+    }
+
+    fn i_op(&mut self) -> AsmResult<()> {
+        let operator = self.expect_token()?;
+        match operator.as_str() {
+            "+=" => self.register_op(0xF01E),
+            ":=" => {
+                let operand = self.expect_token()?;
+                match operand.as_str() {
+                    "hex" => self.register_op(0xF029),
+                    "bighex" => self.register_op(0xF030),
+                    "long" => {
+                        let target = self.expect_token()?;
+                        self.emit(0xF000)?;
+                        match self.value(&target) {
+                            Some(address) => self.emit(address),
+                            None => {
+                                self.fixups.push(Fixup {
+                                    at: self.out.len(),
+                                    kind: FixupKind::Long,
+                                    label: target,
+                                    line: self.line,
+                                });
+                                self.emit(0)
+                            }
+                        }
+                    }
+                    _ => self.address_op_to(0xA000, &operand),
+                }
+            }
+            _ => Err(self.error(format!("unknown operator `i {operator}`"))),
+        }
+    }
+
+    fn save_load(&mut self, save: bool) -> AsmResult<()> {
+        let x = self.expect_register()?;
+        if self.tokens.front().is_some_and(|token| token.text == "-") {
+            self.tokens.pop_front();
+            let y = self.expect_register()?;
+            let n = if save { 2 } else { 3 };
+            return self.emit(0x5000 | x << 8 | y << 4 | n);
+        }
+        let op = if save { 0xF055 } else { 0xF065 };
+        self.emit(op | x << 8)
+    }
+
+    fn condition(&mut self) -> AsmResult<Condition> {
+        let x = self.expect_register()?;
+        let operator = self.expect_token()?;
+        match operator.as_str() {
+            "key" => return Ok(Condition::Key(x)),
+            "-key" => return Ok(Condition::NotKey(x)),
+            "==" | "!=" => {}
+            _ => return Err(self.error(format!("unsupported comparison `{operator}`"))),
+        }
+        let operand = self.expect_token()?;
+        let equal = operator == "==";
+        Ok(match (self.register(&operand), equal) {
+            (Some(y), true) => Condition::Equal(x, y),
+            (Some(y), false) => Condition::NotEqual(x, y),
+            (None, true) => Condition::EqualByte(x, self.byte(&operand)?),
+            (None, false) => Condition::NotEqualByte(x, self.byte(&operand)?),
+        })
+    }
+
+    fn if_statement(&mut self) -> AsmResult<()> {
+        let condition = self.condition()?;
+        match self.expect_token()?.as_str() {
+            "then" => self.emit(condition.skip_unless()),
+            "begin" => {
+                // skip the jump past the body when the condition holds
+                self.emit(condition.negate().skip_unless())?;
+                let jump_at = self.out.len();
+                self.emit(0x1000)?;
+                self.blocks.push(Block::If { jump_at });
+                Ok(())
+            }
+            other => Err(self.error(format!("expected `then` or `begin`, found `{other}`"))),
+        }
+    }
+
+    fn else_statement(&mut self) -> AsmResult<()> {
+        let Some(Block::If { jump_at }) = self.blocks.pop() else {
+            return Err(self.error("`else` without `if .. begin`"));
+        };
+        let else_jump = self.out.len();
+        self.emit(0x1000)?;
+        self.patch_jump(jump_at, self.here()?)?;
+        self.blocks.push(Block::Else { jump_at: else_jump });
+        Ok(())
+    }
+
+    fn end_statement(&mut self) -> AsmResult<()> {
+        match self.blocks.pop() {
+            Some(Block::If { jump_at } | Block::Else { jump_at }) => {
+                self.patch_jump(jump_at, self.here()?)
+            }
+            _ => Err(self.error("`end` without `if .. begin`")),
+        }
+    }
+
+    fn while_statement(&mut self) -> AsmResult<()> {
+        let condition = self.condition()?;
+        self.emit(condition.negate().skip_unless())?;
+        let jump_at = self.out.len();
+        self.emit(0x1000)?;
+        match self.blocks.iter_mut().rev().find_map(|block| match block {
+            Block::Loop { exits, .. } => Some(exits),
+            _ => None,
+        }) {
+            Some(exits) => {
+                exits.push(jump_at);
+                Ok(())
+            }
+            None => Err(self.error("`while` outside of `loop`")),
+        }
+    }
+
+    fn again_statement(&mut self) -> AsmResult<()> {
+        let Some(Block::Loop { start, exits }) = self.blocks.pop() else {
+            return Err(self.error("`again` without `loop`"));
+        };
+        self.emit_address(0x1000, start, "loop")?;
+        for jump_at in exits {
+            self.patch_jump(jump_at, self.here()?)?;
+        }
+        Ok(())
+    }
+
+    fn patch_jump(&mut self, at: usize, target: u16) -> AsmResult<()> {
+        if target > 0xFFF {
+            return Err(self.error("end of block is out of 12-bit range"));
+        }
+        self.out[at] = 0x10 | (target >> 8) as u8;
+        self.out[at + 1] = target as u8;
+        Ok(())
+    }
+
+    fn register_op(&mut self, op: u16) -> AsmResult<()> {
+        let x = self.expect_register()?;
+        self.emit(op | x << 8)
+    }
+
+    fn address_op(&mut self, op: u16) -> AsmResult<()> {
+        let target = self.expect_token()?;
+        self.address_op_to(op, &target)
+    }
+
+    fn address_op_to(&mut self, op: u16, target: &str) -> AsmResult<()> {
+        match self.value(target) {
+            Some(address) => self.emit_address(op, address, target),
+            None if is_name(target) => {
+                self.ensure_entry()?;
+                self.fixups.push(Fixup {
+                    at: self.out.len(),
+                    kind: FixupKind::Address,
+                    label: target.to_string(),
+                    line: self.line,
+                });
+                self.emit(op)
+            }
+            None => Err(self.error(format!("unexpected `{target}`"))),
+        }
+    }
+
+    // `op` with a 12-bit address, which not all of XO-CHIP's 64K can be reached by
+    fn emit_address(&mut self, op: u16, address: u16, target: &str) -> AsmResult<()> {
+        if address > 0xFFF {
+            return Err(self.error(format!("`{target}` is out of 12-bit range")));
+        }
+        self.emit(op | address)
+    }
+
+    // Octo jumps to `main` first unless the program starts there
+    fn ensure_entry(&mut self) -> AsmResult<()> {
+        if self.out.is_empty() && !self.labels.contains_key("main") {
+            self.fixups.push(Fixup {
+                at: 0,
+                kind: FixupKind::Address,
+                label: "main".into(),
+                line: self.line,
+            });
+            self.out.extend([0x10, 0x00]);
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, word: u16) -> AsmResult<()> {
+        self.ensure_entry()?;
+        self.out.extend(word.to_be_bytes());
+        self.check_size()
+    }
+
+    fn emit_byte(&mut self, value: u16) -> AsmResult<()> {
+        if value > 0xFF {
+            return Err(self.error(format!("{value} doesn't fit in a byte")));
+        }
+        self.ensure_entry()?;
+        self.out.push(value as u8);
+        self.check_size()
+    }
+
+    fn check_size(&self) -> AsmResult<()> {
+        if self.out.len() > XO_RAM_SIZE - STARTING_ADDRESS as usize {
+            return Err(self.error("program doesn't fit in memory"));
+        }
+        Ok(())
+    }
+
+    // the address after a program filling all of memory is past the last one
+    fn here(&self) -> AsmResult<u16> {
+        u16::try_from(STARTING_ADDRESS as usize + self.out.len())
+            .map_err(|_| self.error("program doesn't fit in memory"))
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        let token = self.tokens.pop_front()?;
+        self.line = token.line;
+        self.expanded_from.clone_from(&token.expanded_from);
+        Some(token)
+    }
+
+    fn expect_token(&mut self) -> AsmResult<String> {
+        self.next_token()
+            .map(|token| token.text)
+            .ok_or_else(|| self.error("unexpected end of program"))
+    }
+
+    fn expect(&mut self, expected: &str) -> AsmResult<()> {
+        let token = self.expect_token()?;
+        if token != expected {
+            return Err(self.error(format!("expected `{expected}`, found `{token}`")));
+        }
+        Ok(())
+    }
+
+    fn expect_name(&mut self) -> AsmResult<String> {
+        let token = self.expect_token()?;
+        if !is_name(&token) || self.register(&token).is_some() {
+            return Err(self.error(format!("`{token}` isn't a valid name")));
+        }
+        Ok(token)
+    }
+
+    fn expect_register(&mut self) -> AsmResult<u16> {
+        let token = self.expect_token()?;
+        self.register(&token)
+            .ok_or_else(|| self.error(format!("expected a register, found `{token}`")))
+    }
+
+    fn expect_value(&mut self) -> AsmResult<u16> {
+        let token = self.expect_token()?;
+        self.value(&token)
+            .ok_or_else(|| self.error(format!("expected a value, found `{token}`")))
+    }
+
+    fn expect_byte(&mut self) -> AsmResult<u16> {
+        let token = self.expect_token()?;
+        self.byte(&token)
+    }
+
+    fn expect_nibble(&mut self) -> AsmResult<u16> {
+        let value = self.expect_value()?;
+        if value > 0xF {
+            return Err(self.error(format!("{value} doesn't fit in a nibble")));
+        }
+        Ok(value)
+    }
+
+    fn byte(&self, token: &str) -> AsmResult<u16> {
+        match self.value(token) {
+            Some(value) if value <= 0xFF => Ok(value),
+            Some(value) => Err(self.error(format!("{value} doesn't fit in a byte"))),
+            None => Err(self.error(format!("expected a value, found `{token}`"))),
+        }
+    }
+
+    fn register(&self, token: &str) -> Option<u16> {
+        if let Some(&register) = self.aliases.get(token) {
+            return Some(register);
+        }
+        let digit = token.strip_prefix(['v', 'V'])?;
+        (digit.len() == 1)
+            .then(|| u16::from_str_radix(digit, 16).ok())
+            .flatten()
+    }
+
+    fn value(&self, token: &str) -> Option<u16> {
+        if let Some(&value) = self.consts.get(token) {
+            return Some(value);
+        }
+        if let Some(&address) = self.labels.get(token) {
+            return Some(address);
+        }
+        parse_number(token)
+    }
+
+    fn error(&self, message: impl Into<String>) -> AsmError {
+        AsmError {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+}
+
+// decimal, 0x hex or 0b binary; negative decimals wrap to a byte like in Octo
+fn parse_number(token: &str) -> Option<u16> {
+    if let Some(hex) = token.strip_prefix("0x").or(token.strip_prefix("0X")) {
+        return u16::from_str_radix(hex, 16).ok();
+    }
+    if let Some(binary) = token.strip_prefix("0b") {
+        return u16::from_str_radix(binary, 2).ok();
+    }
+    if let Some(negative) = token.strip_prefix('-') {
+        let value: u16 = negative.parse().ok()?;
+        return (value <= 0x80).then_some((0x100 - value) & 0xFF);
+    }
+    token.parse().ok()
+}
+
+fn is_name(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{disassemble, Platform};
+
+    // xorshift, so the random ROMs are the same every run
+    fn random_rom(seed: &mut u32, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                *seed ^= *seed << 13;
+                *seed ^= *seed >> 17;
+                *seed ^= *seed << 5;
+                *seed as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn disassembly_round_trips() {
+        let mut seed = 0x2545_F491;
+        for platform in Platform::ALL {
+            for len in 1..=300 {
+                let rom = random_rom(&mut seed, len);
+                let source = disassemble(&rom, platform);
+                let assembled = assemble(&source).unwrap_or_else(|err| panic!("{err}\n{source}"));
+                assert_eq!(assembled, rom, "{platform:?}\n{source}");
+            }
+        }
+    }
+
+    // a label past 0xFFF, which only `i := long` can reach
+    fn far_label(code: &str) -> String {
+        format!(
+            ": main\n{code}\n: pad\n{}\n: far\nreturn",
+            "0 ".repeat(0x1000)
+        )
+    }
+
+    #[test]
+    fn rejects_addresses_past_12_bits() {
+        for code in ["far", "jump far", ":call far", "i := far"] {
+            let err = assemble(&far_label(code)).unwrap_err();
+            assert!(err.message.contains("12-bit"), "{code}: {err}");
+        }
+        assert!(assemble(&far_label("i := long far")).is_ok());
+
+        // called by name once defined, and looping back from past 0xFFF
+        let source = format!(": main\n{}\n: far\nreturn\nfar", "0 ".repeat(0x1000));
+        assert!(assemble(&source).unwrap_err().message.contains("12-bit"));
+        let source = format!(": main\n{}\nloop\nagain", "0 ".repeat(0x1000));
+        assert!(assemble(&source).unwrap_err().message.contains("12-bit"));
+        let source = format!(": main\nif v0 == 1 begin\n{}\nend", "0 ".repeat(0x1000));
+        assert!(assemble(&source).unwrap_err().message.contains("12-bit"));
+    }
+
+    #[test]
+    fn rejects_programs_past_the_end_of_memory() {
+        let fill = |bytes: usize| format!(": main\n{}", "0 ".repeat(bytes));
+        let room = XO_RAM_SIZE - STARTING_ADDRESS as usize;
+
+        assert_eq!(assemble(&fill(room)).unwrap().len(), room);
+        let err = assemble(&fill(room + 1)).unwrap_err();
+        assert!(err.message.contains("doesn't fit"), "{err}");
+        // nothing is left to point a label at once memory is full
+        let err = assemble(&format!("{}\n: end", fill(room))).unwrap_err();
+        assert!(err.message.contains("doesn't fit"), "{err}");
+    }
+
+    #[test]
+    fn rejects_recursive_macros() {
+        let err = assemble(":macro forever { v0 += 1 forever }\n: main\nforever").unwrap_err();
+        assert!(err.message.contains("forever"), "{err}");
+
+        let err = assemble(":macro ping { pong }\n:macro pong { ping }\n: main\nping").unwrap_err();
+        assert!(err.message.contains("ping"), "{err}");
+
+        // the same macro twice in a row is fine
+        let rom = assemble(":macro inc { v0 += 1 }\n: main\ninc inc").unwrap();
+        assert_eq!(rom, [0x70, 0x01, 0x70, 0x01]);
+    }
+}
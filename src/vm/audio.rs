@@ -0,0 +1,113 @@
+use crate::vm::core::Emulator;
+use web_sys::{
+    AudioBufferSourceNode, AudioContext, AudioScheduledSourceNode, GainNode, OscillatorType,
+};
+
+const BEEP_FREQUENCY: f32 = 440.0;
+const PATTERN_BITS: usize = 128;
+
+// the pattern and playback rate the current source was built from (None for the plain beep)
+type Voice = Option<(Vec<u8>, f64)>;
+
+/// Plays the sound timer through Web Audio: a square wave beep, or the XO-CHIP audio pattern.
+pub struct Beeper {
+    ctx: AudioContext,
+    gain: GainNode,
+    source: Option<(AudioScheduledSourceNode, Voice)>,
+}
+
+impl Beeper {
+    pub fn new() -> Option<Beeper> {
+        let ctx = AudioContext::new().ok()?;
+        let gain = ctx.create_gain().ok()?;
+        gain.connect_with_audio_node(&ctx.destination()).ok()?;
+        Some(Beeper {
+            ctx,
+            gain,
+            source: None,
+        })
+    }
+
+    /// Browsers keep audio suspended until the page gets a user gesture
+    pub fn resume(&self) {
+        let _ = self.ctx.resume();
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.gain.gain().set_value(volume);
+    }
+
+    /// Starts, stops or retunes the sound to match the machine; called once per frame
+    pub fn update(&mut self, chip8: &Emulator) {
+        if !chip8.is_sound_active() {
+            self.stop();
+            return;
+        }
+
+        let voice: Voice = chip8
+            .audio_pattern()
+            .map(|pattern| (pattern.to_vec(), chip8.audio_playback_rate()));
+        if matches!(&self.source, Some((_, playing)) if *playing == voice) {
+            return;
+        }
+
+        self.stop();
+        let source = match &voice {
+            Some((pattern, rate)) => self.pattern_source(pattern, *rate),
+            None => self.beep_source(),
+        };
+        if let Some(source) = source {
+            if source.start().is_ok() {
+                self.source = Some((source, voice));
+            }
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some((source, _)) = self.source.take() {
+            let _ = source.stop();
+            let _ = source.disconnect();
+        }
+    }
+
+    fn beep_source(&self) -> Option<AudioScheduledSourceNode> {
+        let oscillator = self.ctx.create_oscillator().ok()?;
+        oscillator.set_type(OscillatorType::Square);
+        oscillator.frequency().set_value(BEEP_FREQUENCY);
+        oscillator.connect_with_audio_node(&self.gain).ok()?;
+        Some(oscillator.into())
+    }
+
+    // loops one period of the 1-bit pattern, resampled to the context's sample rate
+    fn pattern_source(&self, pattern: &[u8], rate: f64) -> Option<AudioScheduledSourceNode> {
+        let sample_rate = self.ctx.sample_rate();
+        let length = ((PATTERN_BITS as f64 * sample_rate as f64 / rate).round() as u32).max(1);
+        let samples: Vec<f32> = (0..length)
+            .map(|i| {
+                let bit = (i as f64 * rate / sample_rate as f64) as usize % PATTERN_BITS;
+                let set = (pattern[bit / 8] >> (7 - bit % 8)) & 1 != 0;
+                if set {
+                    0.25
+                } else {
+                    -0.25
+                }
+            })
+            .collect();
+
+        let buffer = self.ctx.create_buffer(1, length, sample_rate).ok()?;
+        buffer.copy_to_channel(&samples, 0).ok()?;
+
+        let source: AudioBufferSourceNode = self.ctx.create_buffer_source().ok()?;
+        source.set_buffer(Some(&buffer));
+        source.set_loop(true);
+        source.connect_with_audio_node(&self.gain).ok()?;
+        Some(source.into())
+    }
+}
+
+impl Drop for Beeper {
+    fn drop(&mut self) {
+        self.stop();
+        let _ = self.ctx.close();
+    }
+}
@@ -0,0 +1,115 @@
+use crate::vm::{PixelBuffer, RenderOptions, Renderer, HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH};
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+const MAX_SCALE: u32 = 12;
+const SCANLINE_COLOR: &str = "rgba(0, 0, 0, 0.35)";
+
+/// Puts each frame into a canvas at the CHIP-8 resolution with `putImageData`, then draws
+/// it onto the visible canvas at the largest integer scale that fits its container.
+pub struct ImageDataRenderer {
+    canvas: HtmlCanvasElement,
+    ctx: CanvasRenderingContext2d,
+    frame: HtmlCanvasElement,
+    frame_ctx: CanvasRenderingContext2d,
+    buffer: PixelBuffer,
+    options: RenderOptions,
+}
+
+impl ImageDataRenderer {
+    pub fn new(canvas: HtmlCanvasElement, options: RenderOptions) -> Option<Self> {
+        let frame: HtmlCanvasElement = web_sys::window()?
+            .document()?
+            .create_element("canvas")
+            .ok()?
+            .dyn_into()
+            .ok()?;
+        Some(Self {
+            ctx: context_2d(&canvas)?,
+            frame_ctx: context_2d(&frame)?,
+            canvas,
+            frame,
+            buffer: PixelBuffer::default(),
+            options,
+        })
+    }
+
+    // sized for hi-res so switching resolution doesn't resize the page; fullscreen fills
+    // the screen, otherwise the container's width decides
+    fn fit(&self) -> u32 {
+        let Some(container) = self.canvas.parent_element() else {
+            return 1;
+        };
+        let fullscreen = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.fullscreen_element())
+            .is_some();
+        // the canvas's border has to fit too
+        let border = self.canvas.offset_width() - self.canvas.client_width();
+        let available = |size: i32| (size - border).max(0) as u32;
+        let width_scale = available(container.client_width()) / HIRES_SCREEN_WIDTH as u32;
+        let scale = if fullscreen {
+            let height_scale = available(container.client_height()) / HIRES_SCREEN_HEIGHT as u32;
+            width_scale.min(height_scale)
+        } else {
+            width_scale
+        };
+        scale.clamp(1, MAX_SCALE)
+    }
+}
+
+impl Renderer for ImageDataRenderer {
+    fn set_options(&mut self, options: RenderOptions) {
+        self.options = options;
+    }
+
+    fn render(&mut self, pixels: &[u8], width: usize, height: usize) {
+        let (width, height) = (width as u32, height as u32);
+        if self.frame.width() != width || self.frame.height() != height {
+            self.frame.set_width(width);
+            self.frame.set_height(height);
+        }
+        let rgba = self.buffer.update(pixels, &self.options);
+        if let Ok(image) = ImageData::new_with_u8_clamped_array_and_sh(Clamped(rgba), width, height)
+        {
+            let _ = self.frame_ctx.put_image_data(&image, 0.0, 0.0);
+        }
+
+        let scale = self.fit();
+        let (canvas_width, canvas_height) = (
+            HIRES_SCREEN_WIDTH as u32 * scale,
+            HIRES_SCREEN_HEIGHT as u32 * scale,
+        );
+        if self.canvas.width() != canvas_width || self.canvas.height() != canvas_height {
+            // resizing also resets the context state
+            self.canvas.set_width(canvas_width);
+            self.canvas.set_height(canvas_height);
+        }
+        self.ctx.set_image_smoothing_enabled(false);
+        let _ = self.ctx.draw_image_with_html_canvas_element_and_dw_and_dh(
+            &self.frame,
+            0.0,
+            0.0,
+            canvas_width as f64,
+            canvas_height as f64,
+        );
+
+        let pixel_height = canvas_height / height;
+        if self.options.scanlines && pixel_height >= 3 {
+            let line = (pixel_height / 3) as f64;
+            self.ctx.set_fill_style_str(SCANLINE_COLOR);
+            for y in 1..=height {
+                let bottom = (y * pixel_height) as f64;
+                self.ctx
+                    .fill_rect(0.0, bottom - line, canvas_width as f64, line);
+            }
+        }
+    }
+}
+
+fn context_2d(canvas: &HtmlCanvasElement) -> Option<CanvasRenderingContext2d> {
+    canvas
+        .get_context("2d")
+        .ok()?
+        .and_then(|ctx| ctx.dyn_into::<CanvasRenderingContext2d>().ok())
+}
@@ -0,0 +1,568 @@
+//! Instruction set conformance checks in the spirit of the community CHIP-8 test ROMs.
+//!
+//! Each program is assembled from Octo source and draws one glyph per `expect`: a tick when
+//! the register holds the expected value, a cross otherwise. The same program is then
+//! assembled with an `expect` that always draws a tick, and the two framebuffers have to hash
+//! the same.
+
+use crate::vm::{assemble, Emulator, Platform, Quirks};
+use sha2::{Digest, Sha256};
+
+const TICKS_PER_FRAME: u32 = 200;
+const FRAMES: usize = 120;
+
+const PRELUDE: &str = "
+:alias check-x vC
+:alias check-y vD
+:alias expected vE
+:macro next-row { check-x := 0 check-y += 5 }
+";
+
+const EXPECT: &str = "
+:macro expect register value {
+  expected := value
+  i := tick
+  if register != expected then i := cross
+  sprite check-x check-y 4
+  check-x += 5
+}
+";
+
+const EXPECT_PASS: &str = "
+:macro expect register value {
+  i := tick
+  sprite check-x check-y 4
+  check-x += 5
+}
+";
+
+const GLYPHS: &str = "
+: tick 0x10 0x20 0xA0 0x40
+: cross 0xA0 0x40 0xA0 0x00
+: scratch 0 0 0 0
+: table 0x11 0x22 0x33 0x44
+: dot 0x80 0x80
+: big
+0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF
+0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF
+";
+
+// sets `result` to whether the pixel at (x, y) is lit on the selected planes, leaving it as
+// it was. The checks that draw finish before the first `expect`, which they'd move or hit.
+const PROBE: &str = "
+:macro probe result x y {
+  v8 := x v9 := y
+  i := dot
+  sprite v8 v9 1
+  result := vF
+  sprite v8 v9 1
+}
+";
+
+// lit pixels in the tick glyph
+const TICK_PIXELS: usize = 5;
+
+fn program(body: &str, expect: &str) -> Vec<u8> {
+    let source = format!("{PRELUDE}{expect}\n: main\n{body}\nloop again\n{GLYPHS}");
+    assemble(&source).unwrap_or_else(|err| panic!("{err}\n{source}"))
+}
+
+// runs whole frames, holding the keys in `keys[frame]` (or the last entry once it runs out)
+fn run(rom: &[u8], platform: Platform, quirks: Quirks, keys: &[u16]) -> Emulator {
+    let mut chip8 = Emulator::with_platform(platform, quirks);
+    chip8.seed_rng(1);
+    chip8.load(rom).unwrap();
+    for frame in 0..FRAMES {
+        let mask = keys.get(frame).or(keys.last()).copied().unwrap_or(0);
+        chip8.set_key_mask(mask);
+        for _ in 0..TICKS_PER_FRAME {
+            chip8.tick().unwrap();
+        }
+        chip8.tick_timers();
+    }
+    chip8
+}
+
+fn screen_hash(chip8: &Emulator) -> String {
+    Sha256::digest(chip8.get_display())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn screen_ascii(chip8: &Emulator) -> String {
+    chip8
+        .get_display()
+        .chunks(chip8.screen_width())
+        .flat_map(|row| {
+            row.iter()
+                .map(|pixel| if *pixel != 0 { '#' } else { '.' })
+                .chain(['\n'])
+        })
+        .collect()
+}
+
+fn assert_conforms(body: &str, platform: Platform, quirks: Quirks, keys: &[u16]) {
+    let actual = run(&program(body, EXPECT), platform, quirks, keys);
+    let reference = run(&program(body, EXPECT_PASS), platform, quirks, keys);
+
+    // a program that stops early would draw the same nothing in both runs
+    let lit = reference.get_display().iter().filter(|p| **p != 0).count();
+    assert_eq!(
+        lit,
+        body.matches("expect ").count() * TICK_PIXELS,
+        "not every check ran\n{}",
+        screen_ascii(&reference)
+    );
+    assert_eq!(
+        screen_hash(&actual),
+        screen_hash(&reference),
+        "failed checks are drawn as crosses\n{}",
+        screen_ascii(&actual)
+    );
+}
+
+#[test]
+fn opcodes() {
+    let body = "
+    v0 := 0x2A       expect v0 0x2A
+    v0 := 0xFF
+    v0 += 2          expect v0 1
+    vF := 3
+    v0 += 0xFF
+    v1 := vF         expect v1 3
+    v1 := 0x5C
+    v0 := v1         expect v0 0x5C
+    next-row
+
+    v0 := 0x0F v1 := 0xF0
+    v0 |= v1         expect v0 0xFF
+    v0 := 0x3C v1 := 0x0F
+    v0 &= v1         expect v0 0x0C
+    v0 := 0x3C v1 := 0xFF
+    v0 ^= v1         expect v0 0xC3
+    v0 := 0x30 v1 := 0x12
+    v0 += v1         expect v0 0x42
+    v0 -= v1         expect v0 0x30
+    v0 =- v1         expect v0 0xE2
+    v0 := 0x06
+    v0 >>= v0        expect v0 0x03
+    v0 <<= v0        expect v0 0x06
+    next-row
+
+    v0 := 5 v1 := 0
+    if v0 != 5 then v1 := 1
+    expect v1 0
+    if v0 == 5 then v1 := 2
+    expect v1 2
+    v2 := 5
+    if v0 != v2 then v1 := 3
+    expect v1 2
+    if v0 == v2 then v1 := 4
+    expect v1 4
+    v2 := 6
+    if v0 == v2 then v1 := 5
+    expect v1 4
+    next-row
+
+    jump after-sub
+    : set-v3 v3 := 9 return
+    : after-sub
+    v3 := 0
+    set-v3           expect v3 9
+    v3 := 0
+    :call set-v3     expect v3 9
+
+    i := table
+    v0 := 2
+    i += v0
+    load v0          expect v0 0x33
+    i := scratch
+    v0 := 0xA1 v1 := 0xB2 v2 := 0xC3
+    save v2
+    v0 := 0 v1 := 0 v2 := 0
+    i := scratch
+    load v2          expect v0 0xA1 expect v1 0xB2 expect v2 0xC3
+    next-row
+
+    v0 := random 0   expect v0 0
+    v0 := random 0x0F
+    v1 := 0xF0
+    v1 &= v0         expect v1 0
+    v0 := 0
+    delay := v0
+    v1 := delay      expect v1 0
+    v0 := 0x0A
+    i := hex v0
+    load v0          expect v0 0xF0
+    ";
+    assert_conforms(body, Platform::Chip8, Quirks::CHIP48, &[]);
+}
+
+#[test]
+fn flags() {
+    let body = "
+    v0 := 0xFF v1 := 2
+    v0 += v1 v2 := vF     expect v0 1 expect v2 1
+    v0 := 1
+    v0 += v1 v2 := vF     expect v0 3 expect v2 0
+    next-row
+
+    v0 := 5 v1 := 3
+    v0 -= v1 v2 := vF     expect v0 2 expect v2 1
+    v0 := 3 v1 := 5
+    v0 -= v1 v2 := vF     expect v0 0xFE expect v2 0
+    v0 := 5 v1 := 5
+    v0 -= v1 v2 := vF     expect v0 0 expect v2 1
+    next-row
+
+    v0 := 3 v1 := 5
+    v0 =- v1 v2 := vF     expect v0 2 expect v2 1
+    v0 := 5 v1 := 3
+    v0 =- v1 v2 := vF     expect v0 0xFE expect v2 0
+    next-row
+
+    v0 := 0x81
+    v0 >>= v0 v2 := vF    expect v0 0x40 expect v2 1
+    v0 >>= v0 v2 := vF    expect v0 0x20 expect v2 0
+    v0 := 0x81
+    v0 <<= v0 v2 := vF    expect v0 0x02 expect v2 1
+    v0 <<= v0 v2 := vF    expect v0 0x04 expect v2 0
+    next-row
+
+    # with vF as the destination the flag wins over the result
+    vF := 0xFF v1 := 2
+    vF += v1              expect vF 1
+    vF := 5 v1 := 3
+    vF -= v1              expect vF 1
+    vF := 3 v1 := 5
+    vF =- v1              expect vF 1
+    vF := 0x81
+    vF >>= vF             expect vF 1
+    vF := 0x40
+    vF <<= vF             expect vF 0
+    ";
+    assert_conforms(body, Platform::Chip8, Quirks::CHIP48, &[]);
+}
+
+// the expected values differ per quirk, so the body is generated for each set
+fn quirks_body(quirks: Quirks) -> String {
+    let vf_reset = if quirks.vf_reset { 0 } else { 7 };
+    let shift = if quirks.shift { 0x04 } else { 0x10 };
+    let load = if quirks.load_store_increment {
+        0x22
+    } else {
+        0x09
+    };
+    let jump = if quirks.jump_with_vx { 2 } else { 1 };
+    let wrap = if quirks.clip { 0 } else { 1 };
+    let delay = if quirks.display_wait { 18 } else { 20 };
+    format!(
+        "
+    # this has to stay in 0x200-0x2FF so the jump quirk reads v2
+    v0 := 0 v2 := 2
+    jump0 jump-target
+    : jump-target
+    jump landed-first
+    v5 := 2 jump jumped
+    : landed-first
+    v5 := 1
+    : jumped
+    expect v5 {jump}
+
+    vF := 7 v0 := 1 v1 := 2
+    v0 |= v1 v2 := vF     expect v2 {vf_reset}
+    vF := 7
+    v0 &= v1 v2 := vF     expect v2 {vf_reset}
+    vF := 7
+    v0 ^= v1 v2 := vF     expect v2 {vf_reset}
+    next-row
+
+    v0 := 0x08 v1 := 0x20
+    v0 >>= v1             expect v0 {shift}
+    v0 := 0x02 v1 := 0x08
+    v0 <<= v1             expect v0 {shift}
+
+    i := table
+    v0 := 0x09
+    save v0
+    load v0               expect v0 {load}
+    i := table
+    v0 := 0x11
+    save v0
+    next-row
+
+    # a sprite hanging off the right edge either wraps into column 0 or is clipped
+    v0 := 60 v1 := 31
+    i := table
+    sprite v0 v1 1
+    v2 := 0
+    sprite v2 v1 1
+    v3 := vF
+    sprite v2 v1 1
+    sprite v0 v1 1        expect v3 {wrap}
+
+    # wait for the start of a frame, then let a blank sprite take its vblank
+    v1 := 1
+    delay := v1
+    loop
+      v1 := delay
+      if v1 != 0 then
+    again
+    v0 := 0
+    i := scratch
+    sprite v0 v0 1
+    v1 := 20
+    delay := v1
+    sprite v0 v0 1
+    sprite v0 v0 1
+    v1 := delay           expect v1 {delay}
+    "
+    )
+}
+
+#[test]
+fn quirks() {
+    for (platform, quirks) in [
+        (Platform::Chip8, Quirks::VIP),
+        (Platform::Chip8, Quirks::CHIP48),
+        (Platform::SuperChip, Quirks::SCHIP),
+        (Platform::XoChip, Quirks::XOCHIP),
+    ] {
+        assert_conforms(&quirks_body(quirks), platform, quirks, &[]);
+    }
+}
+
+#[test]
+fn keypad() {
+    // key 5 held from frame 10 onwards
+    let keys = [[0; 10].as_slice(), &[1 << 5]].concat();
+    let body = "
+    v2 := key             expect v2 5
+    v0 := 5 v1 := 0
+    if v0 key then v1 := 1
+    expect v1 1
+    v1 := 0
+    if v0 -key then v1 := 1
+    expect v1 0
+    next-row
+
+    v0 := 6 v1 := 0
+    if v0 key then v1 := 1
+    expect v1 0
+    if v0 -key then v1 := 1
+    expect v1 1
+    # only the low nibble picks the key
+    v0 := 0x15 v1 := 0
+    if v0 key then v1 := 1
+    expect v1 1
+    ";
+    assert_conforms(body, Platform::Chip8, Quirks::CHIP48, &keys);
+}
+
+#[test]
+fn bcd() {
+    let mut body = String::new();
+    for value in [0u8, 7, 10, 42, 99, 100, 137, 255] {
+        body += &format!(
+            "
+    i := scratch
+    v3 := {value}
+    bcd v3
+    i := scratch
+    load v2
+    expect v0 {} expect v1 {} expect v2 {}
+    next-row
+    ",
+            value / 100,
+            value / 10 % 10,
+            value % 10
+        );
+    }
+    assert_conforms(&body, Platform::Chip8, Quirks::CHIP48, &[]);
+}
+
+// golden hashes of the built-in fonts, which no other check draws directly
+#[test]
+fn fonts() {
+    let small = "
+    v0 := 0 v1 := 0 v2 := 0
+    loop
+      i := hex v0
+      sprite v1 v2 5
+      v1 += 5
+      v0 += 1
+      if v0 == 8 then v2 := 6
+      if v0 == 8 then v1 := 0
+      if v0 != 16 then
+    again
+    ";
+    let chip8 = run(
+        &program(small, EXPECT),
+        Platform::Chip8,
+        Quirks::CHIP48,
+        &[],
+    );
+    assert_eq!(
+        screen_hash(&chip8),
+        "99c464e964986b197c6669a7df6521e0a613cc61b385ae8319bb3cb83878667f",
+        "{}",
+        screen_ascii(&chip8)
+    );
+
+    let big = "
+    hires
+    v0 := 0 v1 := 0 v2 := 0
+    loop
+      i := bighex v0
+      sprite v1 v2 10
+      v1 += 9
+      v0 += 1
+      if v0 != 10 then
+    again
+    ";
+    let chip8 = run(
+        &program(big, EXPECT),
+        Platform::SuperChip,
+        Quirks::SCHIP,
+        &[],
+    );
+    assert_eq!(
+        screen_hash(&chip8),
+        "bdfd78714fb6886a8357ba0a4dec1e2c6017851916702dde2a8570f76795e03d",
+        "{}",
+        screen_ascii(&chip8)
+    );
+}
+
+#[test]
+fn super_chip() {
+    let body = format!(
+        "{PROBE}
+    # lo-res is 64 pixels wide, so column 64 wraps to 0
+    v8 := 64 v9 := 0
+    i := dot
+    sprite v8 v9 1
+    probe vB 0 0
+
+    # switching resolution clears the screen, and past column 63 is still on screen in
+    # hi-res rather than wrapping to column 0
+    hires
+    v8 := 64 v9 := 0
+    i := dot
+    sprite v8 v9 1
+    probe v0 0 0
+    probe v1 64 0
+    clear
+
+    # Dxy0 is a 16x16 sprite
+    v8 := 10 v9 := 10
+    i := big
+    sprite v8 v9 0
+    probe v2 25 25
+    probe v3 26 10
+    probe v4 10 26
+    clear
+
+    v8 := 20 v9 := 20
+    i := dot
+    sprite v8 v9 1
+    scroll-down 3
+    probe v5 20 23
+    probe v6 20 20
+    scroll-right
+    probe v7 24 23
+    scroll-left
+    scroll-left
+    probe vA 16 23
+    clear
+
+    expect v0 0 expect v1 1 expect v2 1 expect v3 0 expect v4 0
+    expect v5 1 expect v6 0 expect v7 1 expect vA 1 expect vB 1
+    next-row
+
+    # RPL user flags survive the registers being overwritten
+    v0 := 0x12 v1 := 0x34 v7 := 0x56
+    saveflags v7
+    v0 := 0 v1 := 0 v7 := 0
+    loadflags v7
+    expect v0 0x12 expect v1 0x34 expect v7 0x56
+    "
+    );
+    assert_conforms(&body, Platform::SuperChip, Quirks::SCHIP, &[]);
+}
+
+#[test]
+fn xo_chip() {
+    let body = format!(
+        "{PROBE}
+    # a dot on plane 2 only
+    plane 2
+    v8 := 5 v9 := 5
+    i := dot
+    sprite v8 v9 1
+    plane 1
+    probe v0 5 5
+    plane 2
+    probe v1 5 5
+
+    # scrolling and clearing only touch the selected planes
+    scroll-up 2
+    probe v2 5 3
+    plane 1
+    v8 := 30 v9 := 30
+    i := dot
+    sprite v8 v9 1
+    plane 2
+    clear
+    probe v3 5 3
+    plane 1
+    probe v4 30 30
+
+    # with both planes selected a sprite takes a row for each
+    plane 3
+    clear
+    v8 := 40 v9 := 20
+    i := dot
+    sprite v8 v9 1
+    plane 2
+    probe v5 40 20
+    plane 3
+    clear
+    plane 1
+
+    expect v0 0 expect v1 1 expect v2 1 expect v3 0 expect v4 1 expect v5 1
+    next-row
+
+    # 5xy2/5xy3 save and load a register range in either order, leaving I alone
+    i := scratch
+    v1 := 0x11 v2 := 0x22 v3 := 0x33
+    save v1 - v3
+    load v3 - v1
+    load v4 - v4
+    expect v1 0x33 expect v2 0x22 expect v3 0x11 expect v4 0x11
+    next-row
+
+    # F000 nnnn loads a 16-bit address, and a skip steps over all 4 bytes of it
+    i := long table
+    load v0
+    expect v0 0x11
+    v0 := 0 v5 := 0
+    if v0 != 0 then i := long scratch
+    v5 := 7
+    expect v5 7
+    i := long table
+    load v0
+    expect v0 0x11
+    next-row
+
+    # all 16 RPL flags
+    v0 := 0xA0 vB := 0xAB
+    saveflags vB
+    v0 := 0 vB := 0
+    loadflags vB
+    expect v0 0xA0 expect vB 0xAB
+    "
+    );
+    assert_conforms(&body, Platform::XoChip, Quirks::XOCHIP, &[]);
+}
@@ -1,17 +1,29 @@
+use crate::vm::{Platform, Quirks, SaveState, VmError, VmResult};
 use rand::random;
 
-const RAM_SIZE: usize = 4096;
+pub const RAM_SIZE: usize = 4096;
+pub const XO_RAM_SIZE: usize = 0x10000;
 const NUM_REGISTERS: usize = 16;
 const STACK_SIZE: usize = 16;
 const NUM_KEYS: usize = 16;
 
 pub const SCREEN_WIDTH: usize = 64;
 pub const SCREEN_HEIGHT: usize = 32;
+pub const HIRES_SCREEN_WIDTH: usize = 128;
+pub const HIRES_SCREEN_HEIGHT: usize = 64;
+
+const SCREEN_SIZE: usize = HIRES_SCREEN_WIDTH * HIRES_SCREEN_HEIGHT;
+const AUDIO_PATTERN_SIZE: usize = 16;
+const DEFAULT_PITCH: u8 = 64;
+const RNG_ZERO_SEED: u32 = 0x2545_F491;
 
 pub struct Emulator {
     pc: u16,
-    ram: [u8; RAM_SIZE],
-    screen: [bool; SCREEN_WIDTH * SCREEN_HEIGHT],
+    ram: Vec<u8>,
+    // each pixel holds one bit per bitplane, so values range over the 4 palette entries
+    screen: [u8; SCREEN_SIZE],
+    hires: bool,
+    planes: u8,
     v_registers: [u8; NUM_REGISTERS],
     i_register: u16,
     stack: [u16; STACK_SIZE],
@@ -19,16 +31,45 @@ pub struct Emulator {
     delay_timer: u8,
     sound_timer: u8,
     keys: [bool; NUM_KEYS],
+    rpl_flags: [u8; NUM_REGISTERS],
+    audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+    has_audio_pattern: bool,
+    pitch: u8,
+    platform: Platform,
+    quirks: Quirks,
+    vblank: bool,
+    exited: bool,
+    rng: u32,
+    // debugger memory-write breakpoints, kept out of save states
+    watchpoints: Vec<usize>,
+    watch_hit: Option<usize>,
 }
 
-const STARTING_ADDRESS: u16 = 0x200;
+/// Where ROMs are loaded and execution begins
+pub const STARTING_ADDRESS: u16 = 0x200;
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Emulator {
     pub fn new() -> Self {
+        Self::with_quirks(Quirks::default())
+    }
+
+    pub fn with_quirks(quirks: Quirks) -> Self {
+        Self::with_platform(Platform::Chip8, quirks)
+    }
+
+    pub fn with_platform(platform: Platform, quirks: Quirks) -> Self {
         let mut new_emu = Self {
             pc: STARTING_ADDRESS,
-            ram: [0; RAM_SIZE],
-            screen: [false; SCREEN_WIDTH * SCREEN_HEIGHT],
+            ram: vec![0; platform.ram_size()],
+            screen: [0; SCREEN_SIZE],
+            hires: false,
+            planes: 1,
             v_registers: [0; NUM_REGISTERS],
             i_register: 0,
             stack: [0; STACK_SIZE],
@@ -36,26 +77,51 @@ impl Emulator {
             delay_timer: 0,
             sound_timer: 0,
             keys: [false; NUM_KEYS],
+            rpl_flags: [0; NUM_REGISTERS],
+            audio_pattern: [0; AUDIO_PATTERN_SIZE],
+            has_audio_pattern: false,
+            pitch: DEFAULT_PITCH,
+            platform,
+            quirks,
+            vblank: false,
+            exited: false,
+            rng: 0,
+            watchpoints: vec![],
+            watch_hit: None,
         };
-        new_emu.ram[0..FONTSET_SIZE].copy_from_slice(&FONTSET);
+        new_emu.seed_rng(random());
+        new_emu.load_fonts();
 
         new_emu
     }
 
-    pub fn pop_stack(&mut self) -> u16 {
+    pub fn pop_stack(&mut self) -> VmResult<u16> {
+        if self.sp == 0 {
+            return Err(VmError::StackUnderflow {
+                pc: self.instruction_address(),
+            });
+        }
         self.sp -= 1;
-        self.stack[self.sp as usize]
+        Ok(self.stack[self.sp as usize])
     }
 
-    pub fn push_stack(&mut self, value: u16) {
+    pub fn push_stack(&mut self, value: u16) -> VmResult<()> {
+        if self.sp as usize >= STACK_SIZE {
+            return Err(VmError::StackOverflow {
+                pc: self.instruction_address(),
+            });
+        }
         self.stack[self.sp as usize] = value;
         self.sp += 1;
+        Ok(())
     }
 
     pub fn reset(&mut self) {
         self.pc = STARTING_ADDRESS;
-        self.ram = [0; RAM_SIZE];
-        self.screen = [false; SCREEN_HEIGHT * SCREEN_WIDTH];
+        self.ram.fill(0);
+        self.screen = [0; SCREEN_SIZE];
+        self.hires = false;
+        self.planes = 1;
         self.v_registers = [0; NUM_REGISTERS];
         self.i_register = 0;
         self.stack = [0; STACK_SIZE];
@@ -63,32 +129,80 @@ impl Emulator {
         self.delay_timer = 0;
         self.sound_timer = 0;
         self.keys = [false; NUM_KEYS];
+        self.audio_pattern = [0; AUDIO_PATTERN_SIZE];
+        self.has_audio_pattern = false;
+        self.pitch = DEFAULT_PITCH;
+        self.vblank = false;
+        self.exited = false;
+        self.load_fonts();
+    }
+
+    fn load_fonts(&mut self) {
         self.ram[0..FONTSET_SIZE].copy_from_slice(&FONTSET);
+        self.ram[BIG_FONTSET_ADDRESS..BIG_FONTSET_ADDRESS + BIG_FONTSET_SIZE]
+            .copy_from_slice(&BIG_FONTSET);
     }
 
-    pub fn tick(&mut self) {
-        let op = self.fetch();
+    pub fn tick(&mut self) -> VmResult<()> {
+        if self.exited {
+            return Ok(());
+        }
+        let op = self.fetch()?;
 
-        self.execute(op);
+        self.execute(op)
     }
 
-    fn execute(&mut self, op: u16) {
+    fn execute(&mut self, op: u16) -> VmResult<()> {
         let digit1 = (op & 0xF000) >> 12;
         let digit2 = (op & 0x0F00) >> 8;
         let digit3 = (op & 0x00F0) >> 4;
         let digit4 = op & 0x000F;
 
+        let schip = self.platform.supports_schip();
+        let xochip = self.platform.supports_xochip();
+
         match (digit1, digit2, digit3, digit4) {
-            (0, 0, 0, 0) => return, // Nop
+            (0, 0, 0, 0) => return Ok(()), // Nop
+            (0, 0, 0xC, _) if schip => {
+                // Scroll down by n pixels
+                self.scroll(0, digit4 as isize);
+            }
+            (0, 0, 0xD, _) if xochip => {
+                // Scroll up by n pixels
+                self.scroll(0, -(digit4 as isize));
+            }
             (0, 0, 0xE, 0) => {
-                // Clear_screen
-                self.screen = [false; SCREEN_HEIGHT * SCREEN_WIDTH];
+                // Clear_screen (only the selected bitplanes)
+                let planes = self.planes;
+                self.screen.iter_mut().for_each(|pixel| *pixel &= !planes);
             }
             (0, 0, 0xE, 0xE) => {
                 // Return from subroutine
-                let ret_addr = self.pop_stack();
+                let ret_addr = self.pop_stack()?;
                 self.pc = ret_addr;
             }
+            (0, 0, 0xF, 0xB) if schip => {
+                // Scroll right by 4 pixels
+                self.scroll(4, 0);
+            }
+            (0, 0, 0xF, 0xC) if schip => {
+                // Scroll left by 4 pixels
+                self.scroll(-4, 0);
+            }
+            (0, 0, 0xF, 0xD) if schip => {
+                // Exit the interpreter
+                self.exited = true;
+            }
+            (0, 0, 0xF, 0xE) if schip => {
+                // Switch to low resolution
+                self.hires = false;
+                self.screen = [0; SCREEN_SIZE];
+            }
+            (0, 0, 0xF, 0xF) if schip => {
+                // Switch to high resolution
+                self.hires = true;
+                self.screen = [0; SCREEN_SIZE];
+            }
             (1, _, _, _) => {
                 // Jump to
                 let destination_address = op & 0x0FFF;
@@ -97,7 +211,7 @@ impl Emulator {
             (2, _, _, _) => {
                 // Call subroutine
                 let subroutine_address = op & 0x0FFF;
-                self.push_stack(self.pc);
+                self.push_stack(self.pc)?;
                 self.pc = subroutine_address;
             }
             (3, _, _, _) => {
@@ -107,7 +221,7 @@ impl Emulator {
                 let num_to_compare = (op & 0x00FF) as u8;
 
                 if register == num_to_compare {
-                    self.pc += 2; // each instructon is 2 bytes
+                    self.skip_next_instruction()?;
                 }
             }
             (4, _, _, _) => {
@@ -117,7 +231,7 @@ impl Emulator {
                 let num_to_compare = (op & 0x00FF) as u8;
 
                 if register != num_to_compare {
-                    self.pc += 2; // each instructon is 2 bytes
+                    self.skip_next_instruction()?;
                 }
             }
             (5, _, _, 0) => {
@@ -126,7 +240,21 @@ impl Emulator {
                 let register_num_y = digit3 as usize;
 
                 if self.v_registers[register_num_x] == self.v_registers[register_num_y] {
-                    self.pc += 2;
+                    self.skip_next_instruction()?;
+                }
+            }
+            (5, _, _, 2) if xochip => {
+                // Save registers VX..=VY (in either order) into ram starting at I
+                let i_register = self.i_register as usize;
+                for (offset, register_num) in register_range(digit2, digit3).enumerate() {
+                    self.write_ram(i_register + offset, self.v_registers[register_num])?;
+                }
+            }
+            (5, _, _, 3) if xochip => {
+                // Load registers VX..=VY (in either order) from ram starting at I
+                let i_register = self.i_register as usize;
+                for (offset, register_num) in register_range(digit2, digit3).enumerate() {
+                    self.v_registers[register_num] = self.read_ram(i_register + offset)?;
                 }
             }
             (6, _, _, _) => {
@@ -154,6 +282,9 @@ impl Emulator {
                 let register_num_y = digit3 as usize;
 
                 self.v_registers[register_num_x] |= self.v_registers[register_num_y];
+                if self.quirks.vf_reset {
+                    self.v_registers[0xF] = 0;
+                }
             }
             (8, _, _, 2) => {
                 // Bitwise AND
@@ -161,6 +292,9 @@ impl Emulator {
                 let register_num_y = digit3 as usize;
 
                 self.v_registers[register_num_x] &= self.v_registers[register_num_y];
+                if self.quirks.vf_reset {
+                    self.v_registers[0xF] = 0;
+                }
             }
             (8, _, _, 3) => {
                 // Bitwise XOR
@@ -168,6 +302,9 @@ impl Emulator {
                 let register_num_y = digit3 as usize;
 
                 self.v_registers[register_num_x] ^= self.v_registers[register_num_y];
+                if self.quirks.vf_reset {
+                    self.v_registers[0xF] = 0;
+                }
             }
             (8, _, _, 4) => {
                 // Sum
@@ -177,8 +314,8 @@ impl Emulator {
                 let (result, has_overflow) = self.v_registers[register_num_x]
                     .overflowing_add(self.v_registers[register_num_y]);
 
-                self.v_registers[0xF] = if has_overflow { 1 } else { 0 };
                 self.v_registers[register_num_x] = result;
+                self.v_registers[0xF] = if has_overflow { 1 } else { 0 };
             }
             (8, _, _, 5) => {
                 // Substract
@@ -188,15 +325,21 @@ impl Emulator {
                 let (result, has_underflow) = self.v_registers[register_num_x]
                     .overflowing_sub(self.v_registers[register_num_y]);
 
-                self.v_registers[0xF] = if has_underflow { 0 } else { 1 };
                 self.v_registers[register_num_x] = result;
+                self.v_registers[0xF] = if has_underflow { 0 } else { 1 };
             }
             (8, _, _, 6) => {
                 // Right shift by one
-                let register_number = digit2 as usize;
-                let last_bit = self.v_registers[register_number] & 1;
-                self.v_registers[register_number] >>= 1;
-                self.v_registers[0xF] = last_bit;
+                let register_num_x = digit2 as usize;
+                let register_num_y = digit3 as usize;
+                let source = if self.quirks.shift {
+                    self.v_registers[register_num_x]
+                } else {
+                    self.v_registers[register_num_y]
+                };
+
+                self.v_registers[register_num_x] = source >> 1;
+                self.v_registers[0xF] = source & 1;
             }
             (8, _, _, 7) => {
                 // Substract register x from register y and save in x
@@ -206,16 +349,21 @@ impl Emulator {
                 let (result, has_underflow) = self.v_registers[register_num_y]
                     .overflowing_sub(self.v_registers[register_num_x]);
 
-                self.v_registers[0xF] = if has_underflow { 0 } else { 1 };
                 self.v_registers[register_num_x] = result;
+                self.v_registers[0xF] = if has_underflow { 0 } else { 1 };
             }
             (8, _, _, 0xE) => {
                 // Left shift by one
-                let register_num = digit2 as usize;
-                let first_bit = (self.v_registers[register_num] >> 7) & 1;
+                let register_num_x = digit2 as usize;
+                let register_num_y = digit3 as usize;
+                let source = if self.quirks.shift {
+                    self.v_registers[register_num_x]
+                } else {
+                    self.v_registers[register_num_y]
+                };
 
-                self.v_registers[0xF] = first_bit;
-                self.v_registers[register_num] <<= 1;
+                self.v_registers[register_num_x] = source << 1;
+                self.v_registers[0xF] = (source >> 7) & 1;
             }
             (9, _, _, 0) => {
                 // Skip next if VX != VY
@@ -223,7 +371,7 @@ impl Emulator {
                 let register_num_y = digit3 as usize;
 
                 if self.v_registers[register_num_x] != self.v_registers[register_num_y] {
-                    self.pc += 2;
+                    self.skip_next_instruction()?;
                 }
             }
             (0xA, _, _, _) => {
@@ -232,47 +380,83 @@ impl Emulator {
                 self.i_register = value;
             }
             (0xB, _, _, _) => {
-                // jump to V0 + value
+                // jump to V0 + value (or VX + value with the jump quirk)
                 let value = op & 0x0FFF;
-                let v0 = self.v_registers[0] as u16;
-                self.pc = v0 + value;
+                let register_num = if self.quirks.jump_with_vx {
+                    digit2 as usize
+                } else {
+                    0
+                };
+                let offset = self.v_registers[register_num] as u16;
+                self.pc = offset + value;
             }
             (0xC, _, _, _) => {
                 // Get random num, then & with value, then save in register
                 let register_num = digit2 as usize;
                 let value = (op & 0x00FF) as u8;
-                let rng: u8 = random();
+                let rng = self.next_random();
                 self.v_registers[register_num] = rng & value;
             }
             (0xD, _, _, _) => {
                 // Draw
-                let draw_x = self.v_registers[digit2 as usize] as u16;
-                let draw_y = self.v_registers[digit3 as usize] as u16;
-                let num_rows = digit4;
+                if self.quirks.display_wait && !self.vblank {
+                    // retry until the next frame starts
+                    self.pc -= 2;
+                    return Ok(());
+                }
+                self.vblank = false;
+
+                let width = self.screen_width();
+                let height = self.screen_height();
+                let draw_x = self.v_registers[digit2 as usize] as usize % width;
+                let draw_y = self.v_registers[digit3 as usize] as usize % height;
+                // Dxy0 draws a 16x16 sprite (two bytes per row) on SUPER-CHIP
+                let (num_rows, sprite_width) = if digit4 == 0 && schip {
+                    (16, 16)
+                } else {
+                    (digit4 as usize, 8)
+                };
+                let bytes_per_row = sprite_width / 8;
+                let sprite_size = num_rows * bytes_per_row;
 
                 let mut flipped = false;
+                // with both XO-CHIP planes selected the sprite data for plane 2 follows plane 1
+                let mut sprite_start = self.i_register as usize;
+
+                for plane in [1u8, 2].into_iter().filter(|p| self.planes & p != 0) {
+                    for row in 0..num_rows {
+                        let sprite_address = sprite_start + row * bytes_per_row;
+                        let mut pixels: u16 = 0;
+                        for byte in 0..bytes_per_row {
+                            pixels = (pixels << 8) | self.read_ram(sprite_address + byte)? as u16;
+                        }
 
-                for row in 0..num_rows {
-                    let sprite_address = self.i_register + row;
-                    let pixels = self.ram[sprite_address as usize];
-
-                    for col in 0..8 {
-                        // get current bit of sprite in ram
-                        let current_bit = (pixels >> (7 - col)) & 1;
-
-                        if current_bit != 0 {
-                            // screen draw location of current bit
-                            let x = (draw_x + col) as usize % SCREEN_WIDTH;
-                            let y = (draw_y + row) as usize % SCREEN_HEIGHT;
-                            // get position in draw array
-                            let screen_index = x + SCREEN_WIDTH * y;
-                            // flipped is used for collision detection (checks if any bit was true before modification)
-                            flipped |= self.screen[screen_index];
-
-                            // toggle
-                            self.screen[screen_index] ^= true;
+                        for col in 0..sprite_width {
+                            // get current bit of sprite in ram
+                            let current_bit = (pixels >> (sprite_width - 1 - col)) & 1;
+
+                            if current_bit != 0 {
+                                // screen draw location of current bit
+                                let mut x = draw_x + col;
+                                let mut y = draw_y + row;
+                                if x >= width || y >= height {
+                                    if self.quirks.clip {
+                                        continue;
+                                    }
+                                    x %= width;
+                                    y %= height;
+                                }
+                                // get position in draw array
+                                let screen_index = x + width * y;
+                                // flipped is used for collision detection (checks if any bit was true before modification)
+                                flipped |= self.screen[screen_index] & plane != 0;
+
+                                // toggle
+                                self.screen[screen_index] ^= plane;
+                            }
                         }
                     }
+                    sprite_start += sprite_size;
                 }
                 if flipped {
                     self.v_registers[0xF] = 1;
@@ -281,25 +465,48 @@ impl Emulator {
                 }
             }
             (0xE, _, 9, 0xE) => {
-                // Skip if Key Pressed
+                // Skip if Key Pressed (only the low nibble selects a key)
                 let register_num = digit2 as usize;
-                let key_num = self.v_registers[register_num] as usize;
+                let key_num = (self.v_registers[register_num] & 0xF) as usize;
 
                 let is_pressed = self.keys[key_num];
                 if is_pressed {
-                    self.pc += 2;
+                    self.skip_next_instruction()?;
                 }
             }
             (0xE, _, 0xA, 1) => {
-                // Skip if Key Not Pressed
+                // Skip if Key Not Pressed (only the low nibble selects a key)
                 let register_num = digit2 as usize;
-                let key_num = self.v_registers[register_num] as usize;
+                let key_num = (self.v_registers[register_num] & 0xF) as usize;
 
                 let is_pressed = self.keys[key_num];
                 if !is_pressed {
-                    self.pc += 2;
+                    self.skip_next_instruction()?;
                 }
             }
+            (0xF, 0, 0, 0) if xochip => {
+                // Long load: I = the 16-bit word following the instruction
+                let address = self.pc as usize;
+                let high = self.read_ram(address)? as u16;
+                let low = self.read_ram(address + 1)? as u16;
+                self.i_register = (high << 8) | low;
+                self.pc = self
+                    .pc
+                    .checked_add(2)
+                    .ok_or(VmError::PcOutOfRange { pc: self.pc })?;
+            }
+            (0xF, _, 0, 1) if xochip => {
+                // Select the bitplanes drawing, clearing and scrolling operate on
+                self.planes = (digit2 & 0x3) as u8;
+            }
+            (0xF, 0, 0, 2) if xochip => {
+                // Load the 16 byte audio pattern from ram at I
+                let i_register = self.i_register as usize;
+                for offset in 0..AUDIO_PATTERN_SIZE {
+                    self.audio_pattern[offset] = self.read_ram(i_register + offset)?;
+                }
+                self.has_audio_pattern = true;
+            }
             (0xF, _, 0, 7) => {
                 // save delay_time in register
                 let register_num = digit2 as usize;
@@ -346,6 +553,12 @@ impl Emulator {
                 let value_address = 5 * value; // each sprite is 5 bytes and start from 0 in ram
                 self.i_register = value_address;
             }
+            (0xF, _, 3, 0) if schip => {
+                // set I_register to the large (8x10) sprite corresponding to the value in register
+                let register_num = digit2 as usize;
+                let value = (self.v_registers[register_num] & 0xF) as usize;
+                self.i_register = (BIG_FONTSET_ADDRESS + 10 * value) as u16;
+            }
             (0xF, _, 3, 3) => {
                 // Binary-Coded Decimal
                 let register_num = digit2 as usize;
@@ -355,16 +568,24 @@ impl Emulator {
                 let tens = ((num / 10.0) % 10.0).floor() as u8;
                 let ones = (num % 10.0) as u8;
 
-                self.ram[self.i_register as usize] = hundreds;
-                self.ram[(self.i_register + 1) as usize] = tens;
-                self.ram[(self.i_register + 2) as usize] = ones;
+                let i_register = self.i_register as usize;
+                self.write_ram(i_register, hundreds)?;
+                self.write_ram(i_register + 1, tens)?;
+                self.write_ram(i_register + 2, ones)?;
+            }
+            (0xF, _, 3, 0xA) if xochip => {
+                // Set the audio pattern playback pitch
+                self.pitch = self.v_registers[digit2 as usize];
             }
             (0xF, _, 5, 5) => {
                 // store values of register from 0 to register_num inclusive into ram
                 let register_num = digit2 as usize;
                 let i_register = self.i_register as usize;
                 for i in 0..=register_num {
-                    self.ram[i_register + i] = self.v_registers[i];
+                    self.write_ram(i_register + i, self.v_registers[i])?;
+                }
+                if self.quirks.load_store_increment {
+                    self.i_register = self.i_register.wrapping_add(register_num as u16 + 1);
                 }
             }
             (0xF, _, 6, 5) => {
@@ -372,51 +593,335 @@ impl Emulator {
                 let register_num = digit2 as usize;
                 let i_register = self.i_register as usize;
                 for i in 0..=register_num {
-                    self.v_registers[i] = self.ram[i_register + i];
+                    self.v_registers[i] = self.read_ram(i_register + i)?;
+                }
+                if self.quirks.load_store_increment {
+                    self.i_register = self.i_register.wrapping_add(register_num as u16 + 1);
                 }
             }
-            (_, _, _, _) => unimplemented!("Unimplemented opcode: {}", op),
+            (0xF, _, 7, 5) if digit2 < self.platform.rpl_flags() as u16 => {
+                // save registers from 0 to register_num inclusive into the RPL user flags
+                let register_num = digit2 as usize;
+                self.rpl_flags[..=register_num].copy_from_slice(&self.v_registers[..=register_num]);
+            }
+            (0xF, _, 8, 5) if digit2 < self.platform.rpl_flags() as u16 => {
+                // load registers from 0 to register_num inclusive from the RPL user flags
+                let register_num = digit2 as usize;
+                self.v_registers[..=register_num].copy_from_slice(&self.rpl_flags[..=register_num]);
+            }
+            (_, _, _, _) => {
+                return Err(VmError::UnknownOpcode {
+                    opcode: op,
+                    pc: self.instruction_address(),
+                })
+            }
         }
+
+        Ok(())
     }
 
-    fn fetch(&mut self) -> u16 {
-        let higher_byte = self.ram[self.pc as usize] as u16;
-        let lower_byte = self.ram[(self.pc + 1) as usize] as u16;
+    fn fetch(&mut self) -> VmResult<u16> {
+        let address = self.pc as usize;
+        if address + 1 >= self.ram.len() {
+            return Err(VmError::PcOutOfRange { pc: self.pc });
+        }
+
+        let higher_byte = self.ram[address] as u16;
+        let lower_byte = self.ram[address + 1] as u16;
         let op = (higher_byte << 8) | lower_byte;
-        self.pc += 2;
-        op
+        self.pc = self
+            .pc
+            .checked_add(2)
+            .ok_or(VmError::PcOutOfRange { pc: self.pc })?;
+        Ok(op)
+    }
+
+    // skips the next instruction, which is 4 bytes long for the XO-CHIP long load
+    fn skip_next_instruction(&mut self) -> VmResult<()> {
+        let address = self.pc as usize;
+        let is_long_load = self.platform.supports_xochip()
+            && self.ram.get(address) == Some(&0xF0)
+            && self.ram.get(address + 1) == Some(&0x00);
+        let length = if is_long_load { 4 } else { 2 };
+
+        self.pc = self
+            .pc
+            .checked_add(length)
+            .ok_or(VmError::PcOutOfRange { pc: self.pc })?;
+        Ok(())
+    }
+
+    // address of the instruction being executed (pc already points past it)
+    fn instruction_address(&self) -> u16 {
+        self.pc.wrapping_sub(2)
+    }
+
+    fn read_ram(&self, address: usize) -> VmResult<u8> {
+        self.ram
+            .get(address)
+            .copied()
+            .ok_or(VmError::MemoryOutOfBounds {
+                address,
+                pc: self.instruction_address(),
+            })
+    }
+
+    fn write_ram(&mut self, address: usize, value: u8) -> VmResult<()> {
+        let pc = self.instruction_address();
+        let cell = self
+            .ram
+            .get_mut(address)
+            .ok_or(VmError::MemoryOutOfBounds { address, pc })?;
+        *cell = value;
+        if !self.watchpoints.is_empty() && self.watchpoints.contains(&address) {
+            self.watch_hit = Some(address);
+        }
+        Ok(())
+    }
+
+    /// Seeds the `Cxnn` generator; the same seed and inputs always produce the same run
+    pub fn seed_rng(&mut self, seed: u32) {
+        // xorshift never leaves the all-zero state
+        self.rng = if seed == 0 { RNG_ZERO_SEED } else { seed };
+    }
+
+    // xorshift32, keeping the high byte
+    fn next_random(&mut self) -> u8 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        (x >> 24) as u8
     }
 
     pub fn tick_timers(&mut self) {
+        self.vblank = true;
+
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
 
         if self.sound_timer > 0 {
-            if self.sound_timer == 1 {
-                // make sound
-            }
             self.sound_timer -= 1;
         }
     }
 
-    pub fn get_display(&self) -> &[bool] {
-        &self.screen
+    /// Pixels of the active resolution; each value indexes a 4-colour palette
+    pub fn get_display(&self) -> &[u8] {
+        &self.screen[..self.screen_width() * self.screen_height()]
+    }
+
+    pub fn screen_width(&self) -> usize {
+        if self.hires {
+            HIRES_SCREEN_WIDTH
+        } else {
+            SCREEN_WIDTH
+        }
+    }
+
+    pub fn screen_height(&self) -> usize {
+        if self.hires {
+            HIRES_SCREEN_HEIGHT
+        } else {
+            SCREEN_HEIGHT
+        }
+    }
+
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    pub fn is_sound_active(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    /// XO-CHIP 1-bit audio pattern, once a ROM has loaded one with `F002`
+    pub fn audio_pattern(&self) -> Option<&[u8]> {
+        self.has_audio_pattern.then_some(&self.audio_pattern[..])
+    }
+
+    /// Rate in bits per second the audio pattern is played back at
+    pub fn audio_playback_rate(&self) -> f64 {
+        4000.0 * 2f64.powf((self.pitch as f64 - 64.0) / 48.0)
+    }
+
+    // shift the selected planes of the active screen by (dx, dy) pixels, clearing vacated pixels
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let width = self.screen_width() as isize;
+        let height = self.screen_height() as isize;
+        let planes = self.planes;
+        let mut scrolled = self.screen;
+
+        for y in 0..height {
+            for x in 0..width {
+                let (src_x, src_y) = (x - dx, y - dy);
+                let moved = if (0..width).contains(&src_x) && (0..height).contains(&src_y) {
+                    self.screen[(src_x + src_y * width) as usize] & planes
+                } else {
+                    0
+                };
+                let pixel = &mut scrolled[(x + y * width) as usize];
+                *pixel = (*pixel & !planes) | moved;
+            }
+        }
+        self.screen = scrolled;
     }
 
     pub fn keypressed(&mut self, index: usize, pressed: bool) {
         self.keys[index] = pressed;
     }
 
-    pub fn load(&mut self, data: &[u8]) {
+    /// Keypad state with bit n set while key n is held
+    pub fn key_mask(&self) -> u16 {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter(|(_, pressed)| **pressed)
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+
+    pub fn set_key_mask(&mut self, mask: u16) {
+        for (i, key) in self.keys.iter_mut().enumerate() {
+            *key = mask & (1 << i) != 0;
+        }
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn i_register(&self) -> u16 {
+        self.i_register
+    }
+
+    pub fn v_registers(&self) -> &[u8] {
+        &self.v_registers
+    }
+
+    /// Return addresses currently on the stack, oldest first
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.sp as usize]
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /// Addresses whose writes are reported through [`Emulator::take_watch_hit`]
+    pub fn set_watchpoints(&mut self, addresses: impl IntoIterator<Item = usize>) {
+        self.watchpoints = addresses.into_iter().collect();
+        self.watch_hit = None;
+    }
+
+    /// Last watched address written since the previous call
+    pub fn take_watch_hit(&mut self) -> Option<usize> {
+        self.watch_hit.take()
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn snapshot(&self) -> SaveState {
+        SaveState {
+            platform: self.platform,
+            pc: self.pc,
+            ram: self.ram.clone(),
+            screen: self.screen.to_vec(),
+            hires: self.hires,
+            planes: self.planes,
+            v_registers: self.v_registers,
+            i_register: self.i_register,
+            stack: self.stack,
+            sp: self.sp,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            keys: self.keys,
+            rpl_flags: self.rpl_flags,
+            audio_pattern: self.has_audio_pattern.then_some(self.audio_pattern),
+            pitch: self.pitch,
+            vblank: self.vblank,
+            exited: self.exited,
+            rng: self.rng,
+        }
+    }
+
+    /// Puts the machine back into a snapshot taken on the same platform
+    pub fn restore(&mut self, state: &SaveState) -> VmResult<()> {
+        if state.platform != self.platform
+            || state.ram.len() != self.ram.len()
+            || state.screen.len() != SCREEN_SIZE
+        {
+            return Err(VmError::SaveStateMismatch {
+                saved: state.platform.name(),
+                running: self.platform.name(),
+            });
+        }
+        state.check()?;
+
+        self.pc = state.pc;
+        self.ram.copy_from_slice(&state.ram);
+        self.screen.copy_from_slice(&state.screen);
+        self.hires = state.hires;
+        self.planes = state.planes;
+        self.v_registers = state.v_registers;
+        self.i_register = state.i_register;
+        self.stack = state.stack;
+        self.sp = state.sp;
+        self.delay_timer = state.delay_timer;
+        self.sound_timer = state.sound_timer;
+        self.keys = state.keys;
+        self.rpl_flags = state.rpl_flags;
+        self.audio_pattern = state.audio_pattern.unwrap_or([0; AUDIO_PATTERN_SIZE]);
+        self.has_audio_pattern = state.audio_pattern.is_some();
+        self.pitch = state.pitch;
+        self.vblank = state.vblank;
+        self.exited = state.exited;
+        self.rng = state.rng;
+        Ok(())
+    }
+
+    pub fn load(&mut self, data: &[u8]) -> VmResult<()> {
         let start = STARTING_ADDRESS as usize;
         let end = start + data.len();
+        if end > self.ram.len() {
+            return Err(VmError::RomTooLarge {
+                size: data.len(),
+                max: self.ram.len() - start,
+            });
+        }
 
         self.ram[start..end].copy_from_slice(data);
+        Ok(())
+    }
+}
+
+// registers VX..=VY for 5xy2/5xy3, walking backwards when X > Y
+fn register_range(x: u16, y: u16) -> Box<dyn Iterator<Item = usize>> {
+    let (x, y) = (x as usize, y as usize);
+    if x <= y {
+        Box::new(x..=y)
+    } else {
+        Box::new((y..=x).rev())
     }
 }
 
 const FONTSET_SIZE: usize = 80;
+const BIG_FONTSET_ADDRESS: usize = FONTSET_SIZE;
+const BIG_FONTSET_SIZE: usize = 160;
 
 const FONTSET: [u8; FONTSET_SIZE] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
@@ -436,3 +941,60 @@ const FONTSET: [u8; FONTSET_SIZE] = [
     0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
+
+const BIG_FONTSET: [u8; BIG_FONTSET_SIZE] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{Platform, Quirks};
+
+    // fills all of XO-CHIP ram past the start address with `v0 := 0`
+    fn filled_xo_rom() -> Vec<u8> {
+        [0x60, 0x00].repeat((XO_RAM_SIZE - STARTING_ADDRESS as usize) / 2)
+    }
+
+    fn run_to_end(rom: &[u8]) -> VmError {
+        let mut chip8 = Emulator::with_platform(Platform::XoChip, Quirks::XOCHIP);
+        chip8.load(rom).unwrap();
+        for _ in 0..XO_RAM_SIZE {
+            if let Err(err) = chip8.tick() {
+                return err;
+            }
+        }
+        panic!("ran past the end of ram");
+    }
+
+    #[test]
+    fn stops_at_the_end_of_xo_ram() {
+        assert_eq!(
+            run_to_end(&filled_xo_rom()),
+            VmError::PcOutOfRange { pc: 0xFFFE }
+        );
+    }
+
+    #[test]
+    fn stops_after_a_long_load_at_the_end_of_xo_ram() {
+        let mut rom = filled_xo_rom();
+        let len = rom.len();
+        rom[len - 4..].copy_from_slice(&[0xF0, 0x00, 0x12, 0x34]);
+        assert_eq!(run_to_end(&rom), VmError::PcOutOfRange { pc: 0xFFFE });
+    }
+}
@@ -0,0 +1,198 @@
+use crate::vm::{disassemble_around, disassemble_at, Emulator, Instruction, VmResult};
+use std::collections::BTreeSet;
+use std::fmt;
+
+const DISASSEMBLY_BEFORE: usize = 8;
+const DISASSEMBLY_AFTER: usize = 12;
+pub const MEMORY_VIEW_SIZE: usize = 256;
+
+/// Why execution last stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Paused,
+    Step,
+    Breakpoint(u16),
+    Watchpoint(usize),
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Paused => write!(f, "Paused"),
+            StopReason::Step => write!(f, "Stepped"),
+            StopReason::Breakpoint(pc) => write!(f, "Breakpoint at {pc:#05X}"),
+            StopReason::Watchpoint(address) => write!(f, "Memory write at {address:#05X}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCommand {
+    Pause,
+    Continue,
+    Step,
+    StepOver,
+    ToggleBreakpoint(u16),
+    ToggleWatchpoint(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Into,
+    // run until the instruction after a call returns to this stack depth
+    Over { target: u16, depth: usize },
+}
+
+/// Execution control on top of [`Emulator`]: pausing, stepping and breakpoints.
+#[derive(Debug, Default)]
+pub struct Debugger {
+    stopped: Option<StopReason>,
+    step: Option<Step>,
+    breakpoints: BTreeSet<u16>,
+    watchpoints: BTreeSet<usize>,
+}
+
+/// Machine state shown by the debugger panel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugSnapshot {
+    pub pc: u16,
+    pub i_register: u16,
+    pub v_registers: Vec<u8>,
+    pub stack: Vec<u16>,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub disassembly: Vec<Instruction>,
+    pub memory_base: usize,
+    pub memory: Vec<u8>,
+    pub stopped: Option<StopReason>,
+    pub breakpoints: Vec<u16>,
+    pub watchpoints: Vec<usize>,
+}
+
+impl Debugger {
+    pub fn command(&mut self, chip8: &mut Emulator, command: DebugCommand) {
+        match command {
+            DebugCommand::Pause => {
+                self.step = None;
+                self.stopped.get_or_insert(StopReason::Paused);
+            }
+            DebugCommand::Continue => {
+                self.step = None;
+                self.stopped = None;
+            }
+            DebugCommand::Step => self.step = Some(Step::Into),
+            DebugCommand::StepOver => {
+                let next = disassemble_at(chip8.ram(), chip8.pc() as usize, chip8.platform());
+                self.step = Some(Step::Over {
+                    target: chip8.pc().wrapping_add(next.bytes.len() as u16),
+                    depth: chip8.stack().len(),
+                });
+            }
+            DebugCommand::ToggleBreakpoint(address) => {
+                if !self.breakpoints.remove(&address) {
+                    self.breakpoints.insert(address);
+                }
+            }
+            DebugCommand::ToggleWatchpoint(address) => {
+                if !self.watchpoints.remove(&address) {
+                    self.watchpoints.insert(address);
+                }
+                self.sync_watchpoints(chip8);
+            }
+        }
+    }
+
+    /// Hands the watchpoints to a freshly created machine
+    pub fn sync_watchpoints(&self, chip8: &mut Emulator) {
+        chip8.set_watchpoints(self.watchpoints.iter().copied());
+    }
+
+    /// Whether execution has to go through [`Debugger::run`] instead of running freely
+    pub fn is_active(&self) -> bool {
+        self.stopped.is_some()
+            || self.step.is_some()
+            || !self.breakpoints.is_empty()
+            || !self.watchpoints.is_empty()
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.is_some() && self.step.is_none()
+    }
+
+    /// Runs up to `ticks` instructions, stopping early at breakpoints; returns whether the
+    /// whole frame ran, so the caller knows to advance the timers
+    pub fn run(&mut self, chip8: &mut Emulator, ticks: u32) -> VmResult<bool> {
+        match self.step.take() {
+            Some(Step::Into) => {
+                chip8.tick()?;
+                chip8.take_watch_hit();
+                self.stopped = Some(StopReason::Step);
+                return Ok(false);
+            }
+            Some(Step::Over { target, depth }) => {
+                for _ in 0..ticks {
+                    chip8.tick()?;
+                    if chip8.pc() == target && chip8.stack().len() <= depth {
+                        self.stopped = Some(StopReason::Step);
+                        return Ok(false);
+                    }
+                    if let Some(reason) = self.check(chip8) {
+                        self.stopped = Some(reason);
+                        return Ok(false);
+                    }
+                }
+                // a long call keeps running frame by frame, timers included
+                self.step = Some(Step::Over { target, depth });
+                return Ok(true);
+            }
+            None => {}
+        }
+
+        if self.stopped.is_some() {
+            return Ok(false);
+        }
+        for _ in 0..ticks {
+            chip8.tick()?;
+            if let Some(reason) = self.check(chip8) {
+                self.stopped = Some(reason);
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    // breakpoints stop before the instruction at pc runs
+    fn check(&self, chip8: &mut Emulator) -> Option<StopReason> {
+        if let Some(address) = chip8.take_watch_hit() {
+            return Some(StopReason::Watchpoint(address));
+        }
+        self.breakpoints
+            .contains(&chip8.pc())
+            .then(|| StopReason::Breakpoint(chip8.pc()))
+    }
+
+    pub fn snapshot(&self, chip8: &Emulator, memory_base: usize) -> DebugSnapshot {
+        let ram = chip8.ram();
+        let memory_base = memory_base.min(ram.len().saturating_sub(MEMORY_VIEW_SIZE));
+        DebugSnapshot {
+            pc: chip8.pc(),
+            i_register: chip8.i_register(),
+            v_registers: chip8.v_registers().to_vec(),
+            stack: chip8.stack().to_vec(),
+            delay_timer: chip8.delay_timer(),
+            sound_timer: chip8.sound_timer(),
+            disassembly: disassemble_around(
+                ram,
+                chip8.pc(),
+                chip8.platform(),
+                DISASSEMBLY_BEFORE,
+                DISASSEMBLY_AFTER,
+            ),
+            memory_base,
+            memory: ram[memory_base..memory_base + MEMORY_VIEW_SIZE].to_vec(),
+            stopped: self.stopped.filter(|_| self.step.is_none()),
+            breakpoints: self.breakpoints.iter().copied().collect(),
+            watchpoints: self.watchpoints.iter().copied().collect(),
+        }
+    }
+}
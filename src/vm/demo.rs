@@ -0,0 +1,44 @@
+use crate::vm::{Replay, VmError, VmResult};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Bumped whenever the layout of [`Demo`] changes
+pub const DEMO_VERSION: u8 = 1;
+
+/// Largest encoded demo that can be published and served. A demo is the ROM plus one
+/// keypad byte per frame, so a few minutes of play fits easily.
+pub const MAX_DEMO_BYTES: usize = 1024 * 1024;
+
+/// Unencrypted attract-mode reel shown on a game's store card: a demo ROM, which can be
+/// a cut-down build of the game, and a replay recorded on it.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct Demo {
+    pub rom: Vec<u8>,
+    pub replay: Replay,
+}
+
+impl Demo {
+    pub fn new(rom: Vec<u8>, replay: Replay) -> VmResult<Demo> {
+        replay.check_rom(&rom)?;
+        Ok(Demo { rom, replay })
+    }
+
+    /// Version byte followed by the borsh encoded demo
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![DEMO_VERSION];
+        // writing into a Vec can't fail
+        self.serialize(&mut bytes)
+            .expect("demo serialization failed");
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> VmResult<Demo> {
+        match bytes.split_first() {
+            Some((&DEMO_VERSION, body)) => {
+                let demo = Demo::try_from_slice(body).map_err(|_| VmError::CorruptDemo)?;
+                Demo::new(demo.rom, demo.replay)
+            }
+            Some((&version, _)) => Err(VmError::UnsupportedDemo { version }),
+            None => Err(VmError::CorruptDemo),
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::vm::{Platform, STARTING_ADDRESS};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// One decoded instruction, or the raw bytes when they aren't a valid opcode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub text: String,
+}
+
+/// Decodes the instruction at `address` into an Octo-style mnemonic
+pub fn disassemble_at(ram: &[u8], address: usize, platform: Platform) -> Instruction {
+    decode_at(ram, address, platform, &|target| format!("0x{target:03X}"))
+}
+
+/// Disassembly of the instructions leading up to and following `pc`, assuming the
+/// preceding ones are 2 bytes long
+pub fn disassemble_around(
+    ram: &[u8],
+    pc: u16,
+    platform: Platform,
+    before: usize,
+    after: usize,
+) -> Vec<Instruction> {
+    let mut address = (pc as usize).saturating_sub(before * 2);
+    let mut listing = vec![];
+    while address < ram.len() && listing.len() < before + after + 1 {
+        let instruction = disassemble_at(ram, address, platform);
+        address += instruction.bytes.len();
+        listing.push(instruction);
+    }
+    listing
+}
+
+/// Octo source for a whole ROM, with labels for the jump, call and `i :=` targets inside it.
+///
+/// Every line assembles back to exactly the bytes it came from.
+pub fn disassemble(rom: &[u8], platform: Platform) -> String {
+    let start = STARTING_ADDRESS as usize;
+    let mut image = vec![0; start + rom.len()];
+    image[start..].copy_from_slice(rom);
+
+    // first pass: instruction boundaries and the targets that deserve a label
+    let mut boundaries = vec![];
+    let mut targets = BTreeMap::new();
+    let mut address = start;
+    while address < image.len() {
+        let instruction = disassemble_at(&image, address, platform);
+        if let Some((target, kind)) = target_of(&instruction.bytes) {
+            targets.entry(target).or_insert(kind);
+        }
+        boundaries.push(address as u16);
+        address += instruction.bytes.len();
+    }
+
+    let mut labels: BTreeMap<u16, String> = targets
+        .into_iter()
+        .filter(|(target, _)| boundaries.binary_search(target).is_ok())
+        .map(|(target, kind)| (target, format!("{kind}_{target:03X}")))
+        .collect();
+    labels.insert(STARTING_ADDRESS, "main".into());
+
+    let name = |target: u16| {
+        labels
+            .get(&target)
+            .cloned()
+            .unwrap_or_else(|| format!("0x{target:03X}"))
+    };
+    let mut source = String::new();
+    for &address in &boundaries {
+        if let Some(label) = labels.get(&address) {
+            let _ = writeln!(source, ": {label}");
+        }
+        let instruction = decode_at(&image, address as usize, platform, &name);
+        let _ = writeln!(source, "\t{:<24}# 0x{address:03X}", instruction.text);
+    }
+    source
+}
+
+// label prefix for what an instruction points at
+fn target_of(bytes: &[u8]) -> Option<(u16, &'static str)> {
+    match *bytes {
+        [0xF0, 0x00, high, low] => Some((u16::from_be_bytes([high, low]), "data")),
+        [high, low] => {
+            let nnn = u16::from_be_bytes([high, low]) & 0x0FFF;
+            match high >> 4 {
+                0x1 | 0xB => Some((nnn, "label")),
+                0x2 => Some((nnn, "sub")),
+                0xA => Some((nnn, "data")),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// `name` renders jump, call and `i :=` targets
+fn decode_at(
+    ram: &[u8],
+    address: usize,
+    platform: Platform,
+    name: &dyn Fn(u16) -> String,
+) -> Instruction {
+    let (Some(&high), Some(&low)) = (ram.get(address), ram.get(address + 1)) else {
+        return data(address, ram.get(address..).unwrap_or_default());
+    };
+    let op = u16::from_be_bytes([high, low]);
+
+    // XO-CHIP `i := long nnnn` carries a second word
+    if platform.supports_xochip() && op == 0xF000 {
+        return match ram.get(address + 2..address + 4) {
+            Some(operand) => Instruction {
+                address: address as u16,
+                bytes: ram[address..address + 4].to_vec(),
+                text: format!(
+                    "i := long {}",
+                    name(u16::from_be_bytes([operand[0], operand[1]]))
+                ),
+            },
+            None => data(address, &[high, low]),
+        };
+    }
+
+    match mnemonic(op, platform, name) {
+        Some(text) => Instruction {
+            address: address as u16,
+            bytes: vec![high, low],
+            text,
+        },
+        None => data(address, &[high, low]),
+    }
+}
+
+fn data(address: usize, bytes: &[u8]) -> Instruction {
+    Instruction {
+        address: address as u16,
+        bytes: bytes.to_vec(),
+        text: bytes
+            .iter()
+            .map(|byte| format!("0x{byte:02X}"))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+// mirrors the opcode space `Emulator::execute` accepts on `platform`
+fn mnemonic(op: u16, platform: Platform, name: &dyn Fn(u16) -> String) -> Option<String> {
+    let digit1 = (op & 0xF000) >> 12;
+    let x = (op & 0x0F00) >> 8;
+    let y = (op & 0x00F0) >> 4;
+    let n = op & 0x000F;
+    let nn = op & 0x00FF;
+    let nnn = op & 0x0FFF;
+
+    let schip = platform.supports_schip();
+    let xochip = platform.supports_xochip();
+
+    let text = match (digit1, x, y, n) {
+        (0, 0, 0xC, _) if schip => format!("scroll-down {n}"),
+        (0, 0, 0xD, _) if xochip => format!("scroll-up {n}"),
+        (0, 0, 0xE, 0) => "clear".into(),
+        (0, 0, 0xE, 0xE) => "return".into(),
+        (0, 0, 0xF, 0xB) if schip => "scroll-right".into(),
+        (0, 0, 0xF, 0xC) if schip => "scroll-left".into(),
+        (0, 0, 0xF, 0xD) if schip => "exit".into(),
+        (0, 0, 0xF, 0xE) if schip => "lores".into(),
+        (0, 0, 0xF, 0xF) if schip => "hires".into(),
+        (1, _, _, _) => format!("jump {}", name(nnn)),
+        (2, _, _, _) => format!(":call {}", name(nnn)),
+        (3, _, _, _) => format!("if v{x:x} != 0x{nn:02X} then"),
+        (4, _, _, _) => format!("if v{x:x} == 0x{nn:02X} then"),
+        (5, _, _, 0) => format!("if v{x:x} != v{y:x} then"),
+        (5, _, _, 2) if xochip => format!("save v{x:x} - v{y:x}"),
+        (5, _, _, 3) if xochip => format!("load v{x:x} - v{y:x}"),
+        (6, _, _, _) => format!("v{x:x} := 0x{nn:02X}"),
+        (7, _, _, _) => format!("v{x:x} += 0x{nn:02X}"),
+        (8, _, _, 0) => format!("v{x:x} := v{y:x}"),
+        (8, _, _, 1) => format!("v{x:x} |= v{y:x}"),
+        (8, _, _, 2) => format!("v{x:x} &= v{y:x}"),
+        (8, _, _, 3) => format!("v{x:x} ^= v{y:x}"),
+        (8, _, _, 4) => format!("v{x:x} += v{y:x}"),
+        (8, _, _, 5) => format!("v{x:x} -= v{y:x}"),
+        (8, _, _, 6) => format!("v{x:x} >>= v{y:x}"),
+        (8, _, _, 7) => format!("v{x:x} =- v{y:x}"),
+        (8, _, _, 0xE) => format!("v{x:x} <<= v{y:x}"),
+        (9, _, _, 0) => format!("if v{x:x} == v{y:x} then"),
+        (0xA, _, _, _) => format!("i := {}", name(nnn)),
+        (0xB, _, _, _) => format!("jump0 {}", name(nnn)),
+        (0xC, _, _, _) => format!("v{x:x} := random 0x{nn:02X}"),
+        (0xD, _, _, _) => format!("sprite v{x:x} v{y:x} {n}"),
+        (0xE, _, 9, 0xE) => format!("if v{x:x} -key then"),
+        (0xE, _, 0xA, 1) => format!("if v{x:x} key then"),
+        (0xF, _, 0, 1) if xochip => format!("plane {x}"),
+        (0xF, 0, 0, 2) if xochip => "audio".into(),
+        (0xF, _, 0, 7) => format!("v{x:x} := delay"),
+        (0xF, _, 0, 0xA) => format!("v{x:x} := key"),
+        (0xF, _, 1, 5) => format!("delay := v{x:x}"),
+        (0xF, _, 1, 8) => format!("buzzer := v{x:x}"),
+        (0xF, _, 1, 0xE) => format!("i += v{x:x}"),
+        (0xF, _, 2, 9) => format!("i := hex v{x:x}"),
+        (0xF, _, 3, 0) if schip => format!("i := bighex v{x:x}"),
+        (0xF, _, 3, 3) => format!("bcd v{x:x}"),
+        (0xF, _, 3, 0xA) if xochip => format!("pitch := v{x:x}"),
+        (0xF, _, 5, 5) => format!("save v{x:x}"),
+        (0xF, _, 6, 5) => format!("load v{x:x}"),
+        (0xF, _, 7, 5) if (x as usize) < platform.rpl_flags() => format!("saveflags v{x:x}"),
+        (0xF, _, 8, 5) if (x as usize) < platform.rpl_flags() => format!("loadflags v{x:x}"),
+        _ => return None,
+    };
+    Some(text)
+}
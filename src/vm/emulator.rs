@@ -1,96 +1,350 @@
-use crate::vm::core::*;
+use crate::vm::{
+    clamp_ticks_per_frame, connected_gamepads, core::*, rom_hash, Beeper, DebugCommand,
+    DebugSnapshot, Debugger, DisplayOptions, GamepadLayout, ImageDataRenderer, KeyLayout,
+    MachineProfile, RenderOptions, Renderer, Replay, RewindBuffer, SaveState, VmError, VmResult,
+};
 use js_sys::Uint8Array;
-use wasm_bindgen::JsCast;
-use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, KeyboardEvent};
+use leptos::{html, prelude::*};
+use web_sys::KeyboardEvent;
 
-const NEON_COLORS: &[&str] = &["#ff00ff", "#39ff14", "#00d4ff", "#ff1744"];
+/// `KeyboardEvent.code` held to rewind
+pub const REWIND_KEY: &str = "Backspace";
+
+// where keypad input comes from
+enum Session {
+    Live,
+    Recording(Replay),
+    Playing { replay: Replay, frame: usize },
+}
 
 pub struct EmuWasm {
     chip8: Emulator,
-    ctx: CanvasRenderingContext2d,
-    color: &'static str,
+    renderer: Box<dyn Renderer>,
+    key_layout: KeyLayout,
+    gamepad_layout: GamepadLayout,
+    // keys held on the keyboard, and on gamepads as of the last poll
+    keyboard_mask: u16,
+    gamepad_mask: u16,
+    ticks_per_frame: u32,
+    halted: Option<VmError>,
+    beeper: Option<Beeper>,
+    rom: Vec<u8>,
+    session: Session,
+    rewind: RewindBuffer,
+    rewinding: bool,
+    debugger: Debugger,
 }
 
 impl EmuWasm {
-    pub fn new() -> Option<EmuWasm> {
-        let chip8 = Emulator::new();
-        let document = web_sys::window()?.document()?;
-        let canvas = document.get_element_by_id("canvas")?;
-        let canvas: HtmlCanvasElement = canvas.dyn_into::<HtmlCanvasElement>().ok()?;
-        let ctx = canvas
-            .get_context("2d")
-            .ok()?
-            .and_then(|obj| obj.dyn_into::<CanvasRenderingContext2d>().ok())?;
-        Some(EmuWasm { chip8, ctx, color: NEON_COLORS[0] })
+    /// `None` until `canvas` is mounted
+    pub fn new(profile: &MachineProfile, canvas: &NodeRef<html::Canvas>) -> Option<EmuWasm> {
+        Self::with_beeper(profile, canvas, Beeper::new())
+    }
+
+    /// Without an audio context, for previews that are never heard
+    pub fn silent(profile: &MachineProfile, canvas: &NodeRef<html::Canvas>) -> Option<EmuWasm> {
+        Self::with_beeper(profile, canvas, None)
+    }
+
+    fn with_beeper(
+        profile: &MachineProfile,
+        canvas: &NodeRef<html::Canvas>,
+        beeper: Option<Beeper>,
+    ) -> Option<EmuWasm> {
+        let chip8 = Emulator::with_platform(profile.platform, profile.quirks);
+        let canvas = canvas.get_untracked()?;
+        let options = DisplayOptions::default().render_options(profile.palette.as_ref());
+        Some(EmuWasm {
+            chip8,
+            renderer: Box::new(ImageDataRenderer::new(canvas, options)?),
+            key_layout: profile.key_layout.clone(),
+            gamepad_layout: GamepadLayout::default(),
+            keyboard_mask: 0,
+            gamepad_mask: 0,
+            ticks_per_frame: clamp_ticks_per_frame(profile.ticks_per_frame),
+            halted: None,
+            beeper,
+            rom: vec![],
+            session: Session::Live,
+            rewind: RewindBuffer::default(),
+            rewinding: false,
+            debugger: Debugger::default(),
+        })
+    }
+
+    pub fn tick(&mut self) -> VmResult<()> {
+        if let Some(err) = &self.halted {
+            return Err(err.clone());
+        }
+        self.chip8
+            .tick()
+            .inspect_err(|err| self.halted = Some(err.clone()))
+    }
+
+    pub fn halted(&self) -> Option<&VmError> {
+        self.halted.as_ref()
+    }
+
+    pub fn ticks_per_frame(&self) -> u32 {
+        self.ticks_per_frame
     }
 
-    pub fn tick(&mut self) {
-        self.chip8.tick();
+    /// Instructions run per 60Hz frame, the emulation speed
+    pub fn set_ticks_per_frame(&mut self, ticks_per_frame: u32) {
+        self.ticks_per_frame = clamp_ticks_per_frame(ticks_per_frame);
+    }
+
+    /// Runs one 60Hz frame, feeding recorded input while a replay plays
+    pub fn run_frame(&mut self) -> VmResult<()> {
+        if self.is_rewinding() {
+            self.rewind_frame()?;
+            return Ok(());
+        }
+
+        let mut ticks = self.ticks_per_frame;
+        match &mut self.session {
+            Session::Live => {}
+            Session::Recording(replay) => {
+                // a recording keeps the speed it started at, or it wouldn't replay
+                replay.frames.push(self.chip8.key_mask());
+                ticks = replay.ticks_per_frame;
+            }
+            Session::Playing { replay, frame } => match replay.frames.get(*frame) {
+                Some(&mask) => {
+                    self.chip8.set_key_mask(mask);
+                    ticks = replay.ticks_per_frame;
+                    *frame += 1;
+                }
+                None => {
+                    self.chip8.set_key_mask(0);
+                    self.session = Session::Live;
+                }
+            },
+        }
+
+        // the debugger only drives live play, recordings always run whole frames
+        if matches!(self.session, Session::Live) && self.debugger.is_active() {
+            let completed = self
+                .debugger
+                .run(&mut self.chip8, ticks)
+                .inspect_err(|err| self.halted = Some(err.clone()))?;
+            if !completed {
+                if let Some(beeper) = self.beeper.as_mut() {
+                    beeper.stop();
+                }
+                return Ok(());
+            }
+        } else {
+            for _ in 0..ticks {
+                self.tick()?;
+            }
+        }
+        self.tick_timers();
+        if matches!(self.session, Session::Live) {
+            self.rewind.push(self.chip8.snapshot());
+        }
+        Ok(())
+    }
+
+    // steps back one frame, keeping the keys the player is holding now
+    fn rewind_frame(&mut self) -> VmResult<()> {
+        let keys = self.chip8.key_mask();
+        if let Some(state) = self.rewind.pop() {
+            self.chip8.restore(state)?;
+            self.chip8.set_key_mask(keys);
+            self.halted = None;
+        }
+        if let Some(beeper) = self.beeper.as_mut() {
+            beeper.update(&self.chip8);
+        }
+        Ok(())
+    }
+
+    /// Rewind is only available in live play, since it would desync recordings
+    pub fn is_rewinding(&self) -> bool {
+        self.rewinding && matches!(self.session, Session::Live)
     }
 
     pub fn tick_timers(&mut self) {
         self.chip8.tick_timers();
+        if let Some(beeper) = self.beeper.as_mut() {
+            beeper.update(&self.chip8);
+        }
     }
 
-    pub fn reset(&mut self) {
-        self.chip8.reset();
+    pub fn set_volume(&self, volume: f32) {
+        if let Some(beeper) = &self.beeper {
+            beeper.set_volume(volume);
+        }
+    }
+
+    /// Silences a beep that would otherwise hold while the game is paused
+    pub fn pause_audio(&mut self) {
+        if let Some(beeper) = self.beeper.as_mut() {
+            beeper.stop();
+        }
     }
 
+    pub fn resume_audio(&self) {
+        if let Some(beeper) = &self.beeper {
+            beeper.resume();
+        }
+    }
+
+    /// Only sees keys typed into its own screen, so bound keys don't also scroll the page
     pub fn keypress(&mut self, evt: KeyboardEvent, pressed: bool) {
-        let key = evt.key();
-        if let Some(k) = key_to_btn(&key) {
-            self.chip8.keypressed(k, pressed);
+        let code = evt.code();
+        if code == REWIND_KEY {
+            evt.prevent_default();
+            self.rewinding = pressed;
+            return;
+        }
+        if let Some(button) = self.key_layout.button_for(&code) {
+            evt.prevent_default();
+            if pressed {
+                self.keyboard_mask |= 1 << button;
+            } else {
+                self.keyboard_mask &= !(1 << button);
+            }
+            self.press_button(button, pressed);
+        }
+    }
+
+    /// Lets go of the keyboard when the screen loses focus, since its key-ups go elsewhere
+    pub fn release_keyboard(&mut self) {
+        self.rewinding = false;
+        let held = std::mem::take(&mut self.keyboard_mask);
+        for button in (0..16).filter(|button| held & (1 << button) != 0) {
+            self.press_button(button, false);
+        }
+    }
+
+    /// Presses a CHIP-8 key directly, as the on-screen keypad does
+    pub fn press_button(&mut self, button: usize, pressed: bool) {
+        if !self.is_replaying() {
+            self.chip8.keypressed(button, pressed);
         }
     }
 
-    pub fn load_game(&mut self, data: Uint8Array) {
-        let idx = (js_sys::Math::random() * NEON_COLORS.len() as f64) as usize;
-        self.color = NEON_COLORS[idx.min(NEON_COLORS.len() - 1)];
-        self.chip8.load(&data.to_vec());
+    pub fn set_key_layout(&mut self, key_layout: KeyLayout) {
+        self.key_layout = key_layout;
     }
 
-    pub fn draw_screen(&mut self, scale: usize) {
-        let disp = self.chip8.get_display();
-        let canvas_w = (SCREEN_WIDTH * scale) as f64;
-        let canvas_h = (SCREEN_HEIGHT * scale) as f64;
+    pub fn set_gamepad_layout(&mut self, gamepad_layout: GamepadLayout) {
+        self.gamepad_layout = gamepad_layout;
+    }
 
-        self.ctx.set_fill_style_str("black");
-        self.ctx.fill_rect(0.0, 0.0, canvas_w, canvas_h);
-        self.ctx.set_fill_style_str(self.color);
+    /// Presses and releases the keys whose gamepad inputs changed since the last poll
+    pub fn poll_gamepads(&mut self) {
+        let mask = connected_gamepads()
+            .iter()
+            .fold(0, |mask, state| mask | self.gamepad_layout.mask(state));
+        let changed = mask ^ self.gamepad_mask;
+        self.gamepad_mask = mask;
+        for button in (0..16).filter(|button| changed & (1 << button) != 0) {
+            self.press_button(button, mask & (1 << button) != 0);
+        }
+    }
 
-        for i in 0..(SCREEN_WIDTH * SCREEN_HEIGHT) {
-            if disp[i] {
-                let x = i % SCREEN_WIDTH;
-                let y = i / SCREEN_WIDTH;
-                self.ctx.fill_rect(
-                    (x * scale) as f64,
-                    (y * scale) as f64,
-                    scale as f64,
-                    scale as f64,
-                );
+    pub fn load_game(&mut self, data: Uint8Array) -> VmResult<()> {
+        self.rom = data.to_vec();
+        self.rewind.clear();
+        self.chip8
+            .load(&self.rom)
+            .inspect_err(|err| self.halted = Some(err.clone()))
+    }
+
+    // fresh machine with the same configuration and ROM, so a session can be reproduced
+    fn restart(&mut self, seed: u32) -> VmResult<()> {
+        self.chip8 = Emulator::with_platform(self.chip8.platform(), self.chip8.quirks());
+        self.chip8.seed_rng(seed);
+        self.debugger.sync_watchpoints(&mut self.chip8);
+        self.halted = None;
+        self.rewind.clear();
+        self.chip8
+            .load(&self.rom)
+            .inspect_err(|err| self.halted = Some(err.clone()))
+    }
+
+    /// Restarts the game and starts logging input
+    pub fn start_recording(&mut self) -> VmResult<()> {
+        let seed = (js_sys::Math::random() * u32::MAX as f64) as u32;
+        self.restart(seed)?;
+        self.session =
+            Session::Recording(Replay::new(rom_hash(&self.rom), seed, self.ticks_per_frame));
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) -> Option<Replay> {
+        match std::mem::replace(&mut self.session, Session::Live) {
+            Session::Recording(replay) => Some(replay),
+            session => {
+                self.session = session;
+                None
             }
         }
     }
-}
 
-fn key_to_btn(key: &str) -> Option<usize> {
-    match key {
-        "1" => Some(0x1),
-        "2" => Some(0x2),
-        "3" => Some(0x3),
-        "4" => Some(0xC),
-        "q" => Some(0x4),
-        "w" => Some(0x5),
-        "e" => Some(0x6),
-        "r" => Some(0xD),
-        "a" => Some(0x7),
-        "s" => Some(0x8),
-        "d" => Some(0x9),
-        "f" => Some(0xE),
-        "z" => Some(0xA),
-        "x" => Some(0x0),
-        "c" => Some(0xB),
-        "v" => Some(0xF),
-        _ => None,
+    /// Restarts the game and plays `replay` back; live input is ignored until it ends
+    pub fn play_replay(&mut self, mut replay: Replay) -> VmResult<()> {
+        replay.check_rom(&self.rom)?;
+        replay.ticks_per_frame = clamp_ticks_per_frame(replay.ticks_per_frame);
+        self.restart(replay.seed)?;
+        self.session = Session::Playing { replay, frame: 0 };
+        Ok(())
+    }
+
+    pub fn stop_replay(&mut self) {
+        if self.is_replaying() {
+            self.chip8.set_key_mask(0);
+            self.session = Session::Live;
+        }
+    }
+
+    pub fn debug(&mut self, command: DebugCommand) {
+        self.debugger.command(&mut self.chip8, command);
+    }
+
+    /// Drops breakpoints and lets a paused game run again
+    pub fn detach_debugger(&mut self) {
+        self.debugger = Debugger::default();
+        self.debugger.sync_watchpoints(&mut self.chip8);
+    }
+
+    pub fn debug_snapshot(&self, memory_base: usize) -> DebugSnapshot {
+        self.debugger.snapshot(&self.chip8, memory_base)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self.session, Session::Recording(_))
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.session, Session::Playing { .. })
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        self.chip8.snapshot().to_bytes()
+    }
+
+    /// Restoring also clears a halt, since the snapshot predates it
+    pub fn load_state(&mut self, bytes: &[u8]) -> VmResult<()> {
+        let state = SaveState::from_bytes(bytes)?;
+        self.chip8.restore(&state)?;
+        self.halted = None;
+        self.rewind.clear();
+        Ok(())
+    }
+
+    pub fn set_render_options(&mut self, options: RenderOptions) {
+        self.renderer.set_options(options);
+    }
+
+    pub fn draw_screen(&mut self) {
+        self.renderer.render(
+            self.chip8.get_display(),
+            self.chip8.screen_width(),
+            self.chip8.screen_height(),
+        );
     }
 }
@@ -0,0 +1,40 @@
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum VmError {
+    #[error("unknown opcode {opcode:#06X} at {pc:#05X}")]
+    UnknownOpcode { opcode: u16, pc: u16 },
+    #[error("stack overflow at {pc:#05X}")]
+    StackOverflow { pc: u16 },
+    #[error("stack underflow at {pc:#05X}")]
+    StackUnderflow { pc: u16 },
+    #[error("out-of-bounds memory access at {address:#06X} (pc {pc:#05X})")]
+    MemoryOutOfBounds { address: usize, pc: u16 },
+    #[error("program counter out of range: {pc:#06X}")]
+    PcOutOfRange { pc: u16 },
+    #[error("ROM is too large: {size} bytes (max {max})")]
+    RomTooLarge { size: usize, max: usize },
+    #[error("unsupported save state version {version}")]
+    UnsupportedSaveState { version: u8 },
+    #[error("save state is corrupt")]
+    CorruptSaveState,
+    #[error("unsupported replay version {version}")]
+    UnsupportedReplay { version: u8 },
+    #[error("replay is corrupt")]
+    CorruptReplay,
+    #[error("replay was recorded on a different ROM")]
+    ReplayRomMismatch,
+    #[error("unsupported demo version {version}")]
+    UnsupportedDemo { version: u8 },
+    #[error("demo is corrupt")]
+    CorruptDemo,
+    #[error("{ticks_per_frame} instructions per frame is outside 1..={max}")]
+    SpeedOutOfRange { ticks_per_frame: u32, max: u32 },
+    #[error("save state is for {saved}, not {running}")]
+    SaveStateMismatch {
+        saved: &'static str,
+        running: &'static str,
+    },
+}
+
+pub type VmResult<T> = Result<T, VmError>;
@@ -0,0 +1,31 @@
+use crate::vm::GamepadState;
+use wasm_bindgen::JsCast;
+use web_sys::{Gamepad, GamepadButton};
+
+/// Reads every connected gamepad; browsers only list them once a button has been pressed
+pub fn connected_gamepads() -> Vec<GamepadState> {
+    let Some(gamepads) = web_sys::window().and_then(|w| w.navigator().get_gamepads().ok()) else {
+        return vec![];
+    };
+    gamepads
+        .iter()
+        .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+        .filter(|gamepad| gamepad.connected())
+        .map(|gamepad| GamepadState {
+            buttons: gamepad
+                .buttons()
+                .iter()
+                .map(|button| {
+                    button
+                        .dyn_into::<GamepadButton>()
+                        .is_ok_and(|button| button.pressed())
+                })
+                .collect(),
+            axes: gamepad
+                .axes()
+                .iter()
+                .map(|axis| axis.as_f64().unwrap_or(0.0))
+                .collect(),
+        })
+        .collect()
+}
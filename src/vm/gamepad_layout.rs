@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How far a stick has to be pushed before it counts as pressed
+pub const AXIS_THRESHOLD: f64 = 0.5;
+
+// names of the buttons in the W3C "standard" gamepad mapping
+const STANDARD_BUTTONS: [&str; 17] = [
+    "A", "B", "X", "Y", "LB", "RB", "LT", "RT", "Back", "Start", "LS", "RS", "Up", "Down", "Left",
+    "Right", "Home",
+];
+
+/// A button or one direction of an axis on a gamepad
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GamepadInput {
+    Button(u8),
+    AxisNegative(u8),
+    AxisPositive(u8),
+}
+
+impl fmt::Display for GamepadInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GamepadInput::Button(index) => match STANDARD_BUTTONS.get(*index as usize) {
+                Some(name) => write!(f, "{name}"),
+                None => write!(f, "Button {index}"),
+            },
+            GamepadInput::AxisNegative(index) => write!(f, "Axis {index} -"),
+            GamepadInput::AxisPositive(index) => write!(f, "Axis {index} +"),
+        }
+    }
+}
+
+/// Buttons and axes of one connected gamepad, as read in a frame
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GamepadState {
+    pub buttons: Vec<bool>,
+    pub axes: Vec<f64>,
+}
+
+impl GamepadState {
+    pub fn is_active(&self, input: GamepadInput) -> bool {
+        match input {
+            GamepadInput::Button(index) => self.buttons.get(index as usize) == Some(&true),
+            GamepadInput::AxisNegative(index) => self
+                .axes
+                .get(index as usize)
+                .is_some_and(|value| *value <= -AXIS_THRESHOLD),
+            GamepadInput::AxisPositive(index) => self
+                .axes
+                .get(index as usize)
+                .is_some_and(|value| *value >= AXIS_THRESHOLD),
+        }
+    }
+
+    /// First input being held, used when binding a key
+    pub fn first_active(&self) -> Option<GamepadInput> {
+        let buttons = (0..self.buttons.len()).map(|i| GamepadInput::Button(i as u8));
+        let axes = (0..self.axes.len()).flat_map(|i| {
+            [
+                GamepadInput::AxisNegative(i as u8),
+                GamepadInput::AxisPositive(i as u8),
+            ]
+        });
+        buttons.chain(axes).find(|input| self.is_active(*input))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamepadBinding {
+    pub input: GamepadInput,
+    /// CHIP-8 key (0x0..=0xF)
+    pub button: u8,
+}
+
+/// Gamepad buttons and stick directions bound to the 16 CHIP-8 keys
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamepadLayout {
+    pub bindings: Vec<GamepadBinding>,
+}
+
+impl GamepadLayout {
+    /// Keypad state with bit n set while an input bound to key n is held
+    pub fn mask(&self, state: &GamepadState) -> u16 {
+        self.bindings
+            .iter()
+            .filter(|binding| state.is_active(binding.input))
+            .fold(0, |mask, binding| mask | 1 << binding.button)
+    }
+
+    pub fn inputs_for(&self, button: u8) -> impl Iterator<Item = GamepadInput> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.button == button)
+            .map(|binding| binding.input)
+    }
+
+    /// Makes `input` the only one for `button`, taking it away from any other button
+    pub fn rebind(&mut self, button: u8, input: GamepadInput) {
+        self.bindings
+            .retain(|binding| binding.button != button && binding.input != input);
+        self.bindings.push(GamepadBinding { input, button });
+    }
+}
+
+/// D-pad and left stick steer with 5/7/8/9 and A fires with 6, like the arrow key layout
+impl Default for GamepadLayout {
+    fn default() -> Self {
+        let table = [
+            (GamepadInput::Button(12), 0x5),
+            (GamepadInput::Button(13), 0x8),
+            (GamepadInput::Button(14), 0x7),
+            (GamepadInput::Button(15), 0x9),
+            (GamepadInput::AxisNegative(1), 0x5),
+            (GamepadInput::AxisPositive(1), 0x8),
+            (GamepadInput::AxisNegative(0), 0x7),
+            (GamepadInput::AxisPositive(0), 0x9),
+            (GamepadInput::Button(0), 0x6),
+            (GamepadInput::Button(1), 0x4),
+            (GamepadInput::Button(8), 0x0),
+            (GamepadInput::Button(9), 0xF),
+        ];
+        Self {
+            bindings: table
+                .into_iter()
+                .map(|(input, button)| GamepadBinding { input, button })
+                .collect(),
+        }
+    }
+}
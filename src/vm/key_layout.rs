@@ -0,0 +1,129 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// CHIP-8 keys in the order they sit on the original 4x4 hex keypad
+pub const KEYPAD_ORDER: [u8; 16] = [
+    0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF,
+];
+
+// the classic hex keypad laid out over the left side of a QWERTY keyboard; codes name
+// physical keys, so the same block is used on AZERTY or Dvorak
+const HEX_KEYPAD: [(&str, u8); 16] = [
+    ("Digit1", 0x1),
+    ("Digit2", 0x2),
+    ("Digit3", 0x3),
+    ("Digit4", 0xC),
+    ("KeyQ", 0x4),
+    ("KeyW", 0x5),
+    ("KeyE", 0x6),
+    ("KeyR", 0xD),
+    ("KeyA", 0x7),
+    ("KeyS", 0x8),
+    ("KeyD", 0x9),
+    ("KeyF", 0xE),
+    ("KeyZ", 0xA),
+    ("KeyX", 0x0),
+    ("KeyC", 0xB),
+    ("KeyV", 0xF),
+];
+
+// arrows and space on top of the hex keypad, for games steering with 5/7/8/9 and firing with 6
+const ARROW_KEYS: [(&str, u8); 5] = [
+    ("ArrowUp", 0x5),
+    ("ArrowLeft", 0x7),
+    ("ArrowDown", 0x8),
+    ("ArrowRight", 0x9),
+    ("Space", 0x6),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    /// `KeyboardEvent.code` value; profiles published before codes stored `key` values
+    #[serde(alias = "key", deserialize_with = "code_from_key")]
+    pub code: String,
+    /// CHIP-8 key (0x0..=0xF)
+    pub button: u8,
+}
+
+/// Keyboard keys bound to the 16 CHIP-8 keys
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyLayout {
+    pub name: String,
+    pub bindings: Vec<KeyBinding>,
+}
+
+impl KeyLayout {
+    pub fn hex_keypad() -> Self {
+        Self::from_table("Hex keypad", HEX_KEYPAD.iter())
+    }
+
+    pub fn arrows() -> Self {
+        Self::from_table("Arrow keys", HEX_KEYPAD.iter().chain(ARROW_KEYS.iter()))
+    }
+
+    pub fn presets() -> Vec<KeyLayout> {
+        vec![Self::hex_keypad(), Self::arrows()]
+    }
+
+    pub fn button_for(&self, code: &str) -> Option<usize> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.code == code)
+            .map(|binding| binding.button as usize)
+    }
+
+    /// Codes bound to a CHIP-8 key
+    pub fn codes_for(&self, button: u8) -> impl Iterator<Item = &str> {
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.button == button)
+            .map(|binding| binding.code.as_str())
+    }
+
+    /// Makes `code` the only key for `button`, taking it away from any other button
+    pub fn rebind(&mut self, button: u8, code: &str) {
+        self.bindings
+            .retain(|binding| binding.button != button && binding.code != code);
+        self.bindings.push(KeyBinding {
+            code: code.to_string(),
+            button,
+        });
+    }
+
+    fn from_table<'a>(name: &str, table: impl Iterator<Item = &'a (&'a str, u8)>) -> Self {
+        Self {
+            name: name.to_string(),
+            bindings: table
+                .map(|(code, button)| KeyBinding {
+                    code: code.to_string(),
+                    button: *button,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Default for KeyLayout {
+    fn default() -> Self {
+        Self::hex_keypad()
+    }
+}
+
+/// Short label for a `KeyboardEvent.code`, e.g. `KeyQ` -> `Q`
+pub fn code_label(code: &str) -> &str {
+    code.strip_prefix("Key")
+        .or_else(|| code.strip_prefix("Digit"))
+        .or_else(|| code.strip_prefix("Arrow"))
+        .unwrap_or(code)
+}
+
+// `KeyboardEvent.key` values of the old QWERTY tables, read as the keys' codes
+fn code_from_key<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let key = String::deserialize(deserializer)?;
+    let mut chars = key.chars();
+    Ok(match (chars.next(), chars.next()) {
+        (Some(' '), None) => "Space".to_string(),
+        (Some(c), None) if c.is_ascii_alphabetic() => format!("Key{}", c.to_ascii_uppercase()),
+        (Some(c), None) if c.is_ascii_digit() => format!("Digit{c}"),
+        _ => key,
+    })
+}
@@ -1,6 +1,51 @@
+mod asm;
+#[cfg(feature = "hydrate")]
+mod audio;
+#[cfg(feature = "hydrate")]
+mod canvas_renderer;
+#[cfg(test)]
+mod conformance;
 mod core;
+mod debugger;
+mod demo;
+mod disasm;
 #[cfg(feature = "hydrate")]
 mod emulator;
+mod error;
+#[cfg(feature = "hydrate")]
+mod gamepad;
+mod gamepad_layout;
+mod key_layout;
+mod platform;
+mod profile;
+mod quirks;
+mod renderer;
+mod replay;
+mod rewind;
+mod save_state;
+mod scheduler;
 
+pub use self::core::*;
+pub use asm::*;
+#[cfg(feature = "hydrate")]
+pub use audio::*;
+#[cfg(feature = "hydrate")]
+pub use canvas_renderer::*;
+pub use debugger::*;
+pub use demo::*;
+pub use disasm::*;
 #[cfg(feature = "hydrate")]
 pub use emulator::*;
+pub use error::*;
+#[cfg(feature = "hydrate")]
+pub use gamepad::*;
+pub use gamepad_layout::*;
+pub use key_layout::*;
+pub use platform::*;
+pub use profile::*;
+pub use quirks::*;
+pub use renderer::*;
+pub use replay::*;
+pub use rewind::*;
+pub use save_state::*;
+pub use scheduler::*;
@@ -0,0 +1,92 @@
+use crate::vm::{Quirks, RAM_SIZE, XO_RAM_SIZE};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Instruction set the emulator decodes.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Platform {
+    #[default]
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl Platform {
+    pub const ALL: [Platform; 3] = [Platform::Chip8, Platform::SuperChip, Platform::XoChip];
+
+    /// The snake_case id the profile is serialized with
+    pub fn id(self) -> &'static str {
+        match self {
+            Platform::Chip8 => "chip8",
+            Platform::SuperChip => "super_chip",
+            Platform::XoChip => "xo_chip",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Platform::Chip8 => "CHIP-8",
+            Platform::SuperChip => "SUPER-CHIP",
+            Platform::XoChip => "XO-CHIP",
+        }
+    }
+
+    /// `00Cn`/`00FB`/`00FC` scrolling, hi-res mode, 16x16 sprites, large font and RPL flags
+    pub fn supports_schip(self) -> bool {
+        matches!(self, Platform::SuperChip | Platform::XoChip)
+    }
+
+    /// 64K memory, `F000 nnnn`, bitplanes, `5xy2`/`5xy3`, `00Dn` and the audio pattern buffer
+    pub fn supports_xochip(self) -> bool {
+        matches!(self, Platform::XoChip)
+    }
+
+    /// Number of RPL user flags `Fx75`/`Fx85` can address
+    pub fn rpl_flags(self) -> usize {
+        match self {
+            Platform::Chip8 => 0,
+            Platform::SuperChip => 8,
+            Platform::XoChip => 16,
+        }
+    }
+
+    pub fn ram_size(self) -> usize {
+        match self {
+            Platform::Chip8 | Platform::SuperChip => RAM_SIZE,
+            Platform::XoChip => XO_RAM_SIZE,
+        }
+    }
+
+    pub fn default_quirks(self) -> Quirks {
+        match self {
+            Platform::Chip8 => Quirks::CHIP48,
+            Platform::SuperChip => Quirks::SCHIP,
+            Platform::XoChip => Quirks::XOCHIP,
+        }
+    }
+}
+
+/// Parses the same snake_case ids the profile is serialized with
+impl FromStr for Platform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Platform::ALL
+            .into_iter()
+            .find(|platform| platform.id() == s)
+            .ok_or_else(|| format!("unknown platform {s}"))
+    }
+}
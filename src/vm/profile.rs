@@ -0,0 +1,65 @@
+use crate::vm::{KeyLayout, Platform, Quirks, VmError, VmResult};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_TICKS_PER_FRAME: u32 = 20;
+/// Fastest speed a profile, replay or the speed slider can ask for
+pub const MAX_TICKS_PER_FRAME: u32 = 1000;
+
+/// Every frame runs this many instructions, so a speed from a profile or replay someone
+/// else made is kept within `1..=MAX_TICKS_PER_FRAME`
+pub fn clamp_ticks_per_frame(ticks_per_frame: u32) -> u32 {
+    ticks_per_frame.clamp(1, MAX_TICKS_PER_FRAME)
+}
+
+/// Named 4-colour palettes: background, plane 1, plane 2 and both XO-CHIP planes
+pub const PALETTES: &[(&str, [&str; 4])] = &[
+    ("Neon magenta", ["black", "#ff00ff", "#00d4ff", "#ffffff"]),
+    ("Neon green", ["black", "#39ff14", "#ff1744", "#ffff00"]),
+    ("Neon blue", ["black", "#00d4ff", "#ff00ff", "#ffffff"]),
+    ("Neon red", ["black", "#ff1744", "#39ff14", "#ffff00"]),
+];
+
+/// How a published ROM expects to be run, stored in the game's off-chain metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MachineProfile {
+    pub platform: Platform,
+    pub ticks_per_frame: u32,
+    pub quirks: Quirks,
+    /// `None` leaves it to the player's display settings
+    pub palette: Option<[String; 4]>,
+    pub key_layout: KeyLayout,
+}
+
+impl MachineProfile {
+    pub fn for_platform(platform: Platform) -> Self {
+        Self {
+            platform,
+            quirks: platform.default_quirks(),
+            ..Default::default()
+        }
+    }
+
+    /// Checked before a profile is published
+    pub fn validate(&self) -> VmResult<()> {
+        if clamp_ticks_per_frame(self.ticks_per_frame) != self.ticks_per_frame {
+            return Err(VmError::SpeedOutOfRange {
+                ticks_per_frame: self.ticks_per_frame,
+                max: MAX_TICKS_PER_FRAME,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for MachineProfile {
+    fn default() -> Self {
+        Self {
+            platform: Platform::default(),
+            ticks_per_frame: DEFAULT_TICKS_PER_FRAME,
+            quirks: Quirks::default(),
+            palette: None,
+            key_layout: KeyLayout::default(),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+/// Behaviour of the CHIP-8 instructions that were interpreted differently across platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Quirks {
+    /// `8xy6`/`8xyE` shift VX in place instead of shifting VY into VX
+    pub shift: bool,
+    /// `Fx55`/`Fx65` leave I pointing past the last register they touched
+    pub load_store_increment: bool,
+    /// `Bnnn` jumps to `xnn + VX` instead of `nnn + V0`
+    pub jump_with_vx: bool,
+    /// `8xy1`/`8xy2`/`8xy3` reset VF to 0
+    pub vf_reset: bool,
+    /// sprites are clipped at the screen edges instead of wrapping around
+    pub clip: bool,
+    /// `Dxyn` waits for the next 60Hz frame before drawing
+    pub display_wait: bool,
+}
+
+impl Quirks {
+    /// Original COSMAC VIP interpreter
+    pub const VIP: Quirks = Quirks {
+        shift: false,
+        load_store_increment: true,
+        jump_with_vx: false,
+        vf_reset: true,
+        clip: true,
+        display_wait: true,
+    };
+
+    /// SUPER-CHIP 1.1 on the HP48
+    pub const SCHIP: Quirks = Quirks {
+        shift: true,
+        load_store_increment: false,
+        jump_with_vx: true,
+        vf_reset: false,
+        clip: true,
+        display_wait: false,
+    };
+
+    /// XO-CHIP as implemented by Octo
+    pub const XOCHIP: Quirks = Quirks {
+        shift: false,
+        load_store_increment: true,
+        jump_with_vx: false,
+        vf_reset: false,
+        clip: false,
+        display_wait: false,
+    };
+
+    /// Modern CHIP-48 style interpreters, and what the emulator has always run
+    pub const CHIP48: Quirks = Quirks {
+        shift: true,
+        load_store_increment: false,
+        jump_with_vx: false,
+        vf_reset: false,
+        clip: false,
+        display_wait: false,
+    };
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::CHIP48
+    }
+}
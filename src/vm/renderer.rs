@@ -0,0 +1,111 @@
+use crate::vm::PALETTES;
+use serde::{Deserialize, Serialize};
+
+// share of its last brightness a pixel keeps each frame after it goes dark
+const PHOSPHOR_DECAY: f32 = 0.55;
+
+pub type Rgb = [u8; 3];
+
+/// Something that shows the emulator's framebuffer.
+pub trait Renderer {
+    fn set_options(&mut self, options: RenderOptions);
+
+    /// `pixels` are palette indices, `width` to a row
+    fn render(&mut self, pixels: &[u8], width: usize, height: usize);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Background, plane 1, plane 2 and both planes
+    pub palette: [Rgb; 4],
+    /// Let pixels fade out over a few frames instead of flickering
+    pub phosphor: bool,
+    pub scanlines: bool,
+}
+
+/// A player's display settings, kept in local storage
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayOptions {
+    /// Name of one of [`PALETTES`]; `None` uses the game's own palette
+    pub palette: Option<String>,
+    pub phosphor: bool,
+    pub scanlines: bool,
+}
+
+impl DisplayOptions {
+    /// Games published without a palette get the first neon one
+    pub fn render_options(&self, game_palette: Option<&[String; 4]>) -> RenderOptions {
+        let named = self
+            .palette
+            .as_deref()
+            .and_then(|name| PALETTES.iter().find(|(n, _)| *n == name));
+        let palette = match (named, game_palette) {
+            (Some((_, colors)), _) => colors.map(parse_color),
+            (None, Some(colors)) => colors.each_ref().map(|color| parse_color(color)),
+            (None, None) => PALETTES[0].1.map(parse_color),
+        };
+        RenderOptions {
+            palette,
+            phosphor: self.phosphor,
+            scanlines: self.scanlines,
+        }
+    }
+}
+
+/// Parses the `#rrggbb`, `#rgb`, `black` and `white` colours palettes are written in
+pub fn parse_color(color: &str) -> Rgb {
+    let channel = |hex: &str| u8::from_str_radix(hex, 16).ok();
+    let parsed = match color.trim() {
+        "white" => Some([0xFF; 3]),
+        hex if hex.len() == 7 && hex.starts_with('#') => (|| {
+            Some([
+                channel(&hex[1..3])?,
+                channel(&hex[3..5])?,
+                channel(&hex[5..7])?,
+            ])
+        })(),
+        hex if hex.len() == 4 && hex.starts_with('#') => (|| {
+            let short = [
+                channel(&hex[1..2])?,
+                channel(&hex[2..3])?,
+                channel(&hex[3..4])?,
+            ];
+            Some(short.map(|c| c * 0x11))
+        })(),
+        _ => None,
+    };
+    parsed.unwrap_or([0; 3])
+}
+
+/// RGBA image of the framebuffer, with pixels lingering when phosphor fade is on
+#[derive(Debug, Default)]
+pub struct PixelBuffer {
+    glow: Vec<[f32; 3]>,
+    rgba: Vec<u8>,
+}
+
+impl PixelBuffer {
+    pub fn update(&mut self, pixels: &[u8], options: &RenderOptions) -> &[u8] {
+        // a resolution switch starts from a clean screen
+        if self.glow.len() != pixels.len() {
+            self.glow = vec![[0.0; 3]; pixels.len()];
+            self.rgba = vec![0xFF; pixels.len() * 4];
+        }
+
+        for (i, pixel) in pixels.iter().enumerate() {
+            let target = options.palette[*pixel as usize & 3];
+            let glow = &mut self.glow[i];
+            for channel in 0..3 {
+                let target = target[channel] as f32;
+                glow[channel] = if options.phosphor && target < glow[channel] {
+                    glow[channel] * PHOSPHOR_DECAY + target * (1.0 - PHOSPHOR_DECAY)
+                } else {
+                    target
+                };
+                self.rgba[i * 4 + channel] = glow[channel] as u8;
+            }
+        }
+        &self.rgba
+    }
+}
@@ -0,0 +1,112 @@
+use crate::vm::{VmError, VmResult};
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha2::{Digest, Sha256};
+
+/// Bumped whenever the layout of [`Replay`] changes
+pub const REPLAY_VERSION: u8 = 1;
+pub const REPLAY_FILE_EXTENSION: &str = "c8replay";
+
+pub fn rom_hash(rom: &[u8]) -> [u8; 32] {
+    Sha256::digest(rom).into()
+}
+
+/// Recorded session: replaying the same ROM from a fresh machine seeded with `seed`,
+/// running `ticks_per_frame` instructions and feeding one keypad bitmask per frame,
+/// reproduces it exactly.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct Replay {
+    pub rom_hash: [u8; 32],
+    pub seed: u32,
+    pub ticks_per_frame: u32,
+    pub frames: Vec<u16>,
+}
+
+impl Replay {
+    pub fn new(rom_hash: [u8; 32], seed: u32, ticks_per_frame: u32) -> Self {
+        Replay {
+            rom_hash,
+            seed,
+            ticks_per_frame,
+            frames: vec![],
+        }
+    }
+
+    /// Version byte followed by the borsh encoded replay
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![REPLAY_VERSION];
+        // writing into a Vec can't fail
+        self.serialize(&mut bytes)
+            .expect("replay serialization failed");
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> VmResult<Replay> {
+        match bytes.split_first() {
+            Some((&REPLAY_VERSION, body)) => {
+                Replay::try_from_slice(body).map_err(|_| VmError::CorruptReplay)
+            }
+            Some((&version, _)) => Err(VmError::UnsupportedReplay { version }),
+            None => Err(VmError::CorruptReplay),
+        }
+    }
+
+    /// Replays only reproduce on the ROM they were recorded on
+    pub fn check_rom(&self, rom: &[u8]) -> VmResult<()> {
+        if self.rom_hash != rom_hash(rom) {
+            return Err(VmError::ReplayRomMismatch);
+        }
+        Ok(())
+    }
+
+    /// Length of the recording in seconds of 60Hz frames
+    pub fn duration_secs(&self) -> f64 {
+        self.frames.len() as f64 / 60.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROM: &[u8] = &[0x60, 0x2A, 0x12, 0x00];
+
+    fn recorded() -> Replay {
+        let mut replay = Replay::new(rom_hash(ROM), 7, 20);
+        replay.frames = vec![0, 0x0001, 0x8000, 0xFFFF, 0];
+        replay
+    }
+
+    #[test]
+    fn round_trips() {
+        let replay = recorded();
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()), Ok(replay));
+    }
+
+    #[test]
+    fn rejects_other_versions_and_corrupt_bytes() {
+        let mut bytes = recorded().to_bytes();
+        assert_eq!(
+            Replay::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(VmError::CorruptReplay)
+        );
+        assert_eq!(Replay::from_bytes(&[]), Err(VmError::CorruptReplay));
+
+        bytes[0] = REPLAY_VERSION + 1;
+        assert_eq!(
+            Replay::from_bytes(&bytes),
+            Err(VmError::UnsupportedReplay {
+                version: REPLAY_VERSION + 1
+            })
+        );
+    }
+
+    #[test]
+    fn only_plays_on_its_rom() {
+        let replay = recorded();
+        assert_eq!(replay.check_rom(ROM), Ok(()));
+        assert_eq!(
+            replay.check_rom(&[0x00, 0xE0]),
+            Err(VmError::ReplayRomMismatch)
+        );
+    }
+}
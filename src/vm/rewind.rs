@@ -0,0 +1,264 @@
+use crate::vm::SaveState;
+use std::collections::VecDeque;
+
+/// Frames the rewind buffer aims to hold, ~30 seconds at 60Hz
+pub const REWIND_FRAMES: usize = 30 * 60;
+/// Upper bound on the memory the buffer may use
+pub const REWIND_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+// bytes a run costs beyond its data: offset and length
+const RUN_OVERHEAD: usize = 2 * size_of::<usize>();
+
+/// Ring buffer of recent machine states.
+///
+/// Only the newest state is kept whole; every older frame is stored as the small patch that
+/// turns the state after it back into it, so the oldest frames can be dropped freely.
+pub struct RewindBuffer {
+    newest: Option<SaveState>,
+    frames: VecDeque<Frame>,
+    // size of the frames, the newest state not included
+    bytes: usize,
+    max_frames: usize,
+    max_bytes: usize,
+}
+
+// the previous state, with ram and screen replaced by patches against the next one
+struct Frame {
+    state: SaveState,
+    ram: Patch,
+    screen: Patch,
+}
+
+impl Frame {
+    fn size(&self) -> usize {
+        size_of::<Frame>() + self.ram.size() + self.screen.size()
+    }
+}
+
+fn state_size(state: &SaveState) -> usize {
+    size_of::<SaveState>() + state.ram.len() + state.screen.len()
+}
+
+// runs of bytes that differ, as (offset, replacement)
+struct Patch(Vec<(usize, Vec<u8>)>);
+
+impl Patch {
+    // patch that turns `from` into `to`; both have the same length
+    fn diff(from: &[u8], to: &[u8]) -> Patch {
+        let mut runs: Vec<(usize, Vec<u8>)> = vec![];
+        let mut i = 0;
+        while i < to.len() {
+            if from[i] == to[i] {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < to.len() && from[i] != to[i] {
+                i += 1;
+            }
+            runs.push((start, to[start..i].to_vec()));
+        }
+        Patch(runs)
+    }
+
+    fn apply(&self, data: &mut [u8]) {
+        for (offset, bytes) in &self.0 {
+            data[*offset..*offset + bytes.len()].copy_from_slice(bytes);
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.0
+            .iter()
+            .map(|(_, bytes)| RUN_OVERHEAD + bytes.len())
+            .sum()
+    }
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        Self::new(REWIND_FRAMES, REWIND_MAX_BYTES)
+    }
+}
+
+impl RewindBuffer {
+    pub fn new(max_frames: usize, max_bytes: usize) -> Self {
+        RewindBuffer {
+            newest: None,
+            frames: VecDeque::new(),
+            bytes: 0,
+            max_frames,
+            max_bytes,
+        }
+    }
+
+    pub fn push(&mut self, state: SaveState) {
+        if let Some(previous) = self.newest.take() {
+            if previous.ram.len() == state.ram.len() && previous.screen.len() == state.screen.len()
+            {
+                let frame = Frame {
+                    ram: Patch::diff(&state.ram, &previous.ram),
+                    screen: Patch::diff(&state.screen, &previous.screen),
+                    state: SaveState {
+                        ram: vec![],
+                        screen: vec![],
+                        ..previous
+                    },
+                };
+                self.bytes += frame.size();
+                self.frames.push_back(frame);
+            } else {
+                // states from different machines can't be patched into each other
+                self.clear();
+            }
+        }
+        self.newest = Some(state);
+
+        while self.frames.len() > self.max_frames || self.bytes() > self.max_bytes {
+            match self.frames.pop_front() {
+                Some(frame) => self.bytes -= frame.size(),
+                None => break,
+            }
+        }
+    }
+
+    /// Steps one frame back, returning the state to restore
+    pub fn pop(&mut self) -> Option<&SaveState> {
+        let frame = self.frames.pop_back()?;
+        self.bytes -= frame.size();
+        let newest = self.newest.as_mut()?;
+
+        frame.ram.apply(&mut newest.ram);
+        frame.screen.apply(&mut newest.screen);
+        let ram = std::mem::take(&mut newest.ram);
+        let screen = std::mem::take(&mut newest.screen);
+        *newest = SaveState {
+            ram,
+            screen,
+            ..frame.state
+        };
+        Some(newest)
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.frames.clear();
+        self.bytes = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Seconds of 60Hz frames that can currently be rewound
+    pub fn seconds(&self) -> f64 {
+        self.frames.len() as f64 / 60.0
+    }
+
+    /// Memory held, the newest state included
+    pub fn bytes(&self) -> usize {
+        self.bytes + self.newest.as_ref().map_or(0, state_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{Emulator, Platform, Quirks};
+
+    // a state whose ram and screen differ from its neighbours by `changed` bytes
+    fn state(platform: Platform, frame: usize, changed: usize) -> SaveState {
+        let mut state = Emulator::with_platform(platform, Quirks::default()).snapshot();
+        for i in 0..changed {
+            let offset = (frame * 31 + i) % state.ram.len();
+            state.ram[offset] = (frame + i) as u8 | 1;
+        }
+        let pixel = frame % state.screen.len();
+        state.screen[pixel] = 1;
+        state.pc = 0x200 + frame as u16;
+        state.v_registers[0] = frame as u8;
+        state.rng = frame as u32;
+        state
+    }
+
+    #[test]
+    fn pops_states_back_in_order() {
+        let mut chip8 = Emulator::with_platform(Platform::Chip8, Quirks::default());
+        // counts in v0 and keeps writing it to memory with a BCD
+        chip8
+            .load(&[0x70, 0x01, 0xA3, 0x00, 0xF0, 0x33, 0x12, 0x00])
+            .unwrap();
+        let mut buffer = RewindBuffer::default();
+        let mut states = vec![];
+        for _ in 0..50 {
+            for _ in 0..3 {
+                chip8.tick().unwrap();
+            }
+            states.push(chip8.snapshot());
+            buffer.push(chip8.snapshot());
+        }
+
+        states.pop();
+        while let Some(expected) = states.pop() {
+            assert_eq!(buffer.pop(), Some(&expected));
+        }
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.pop(), None);
+        // only the state rewound to is left
+        assert_eq!(buffer.bytes(), state_size(&chip8.snapshot()));
+    }
+
+    #[test]
+    fn keeps_at_most_max_frames() {
+        let mut buffer = RewindBuffer::default();
+        for frame in 0..REWIND_FRAMES + 100 {
+            buffer.push(state(Platform::Chip8, frame, 4));
+        }
+        assert_eq!(buffer.seconds(), REWIND_FRAMES as f64 / 60.0);
+
+        // the oldest frames went, the newest ones still restore
+        let newest = REWIND_FRAMES + 99;
+        for frame in (newest - REWIND_FRAMES..newest).rev() {
+            assert_eq!(buffer.pop(), Some(&state(Platform::Chip8, frame, 4)));
+        }
+        assert_eq!(buffer.pop(), None);
+    }
+
+    #[test]
+    fn keeps_within_max_bytes() {
+        let mut buffer = RewindBuffer::default();
+        let ram = Platform::XoChip.ram_size();
+        for frame in 0..200 {
+            buffer.push(state(Platform::XoChip, frame, ram / 2));
+            assert!(buffer.bytes() <= REWIND_MAX_BYTES);
+        }
+        let kept = (buffer.seconds() * 60.0).round() as usize;
+        assert!(kept > 0 && kept < 200);
+        for frame in (199 - kept..199).rev() {
+            assert_eq!(buffer.pop(), Some(&state(Platform::XoChip, frame, ram / 2)));
+        }
+        assert_eq!(buffer.bytes(), state_size(&state(Platform::XoChip, 0, 0)));
+    }
+
+    #[test]
+    fn starts_over_when_the_machine_changes() {
+        let mut buffer = RewindBuffer::default();
+        buffer.push(state(Platform::Chip8, 0, 4));
+        buffer.push(state(Platform::Chip8, 1, 4));
+        buffer.push(state(Platform::XoChip, 2, 4));
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.bytes(), state_size(&state(Platform::XoChip, 2, 4)));
+    }
+
+    #[test]
+    fn counts_the_newest_state_towards_max_bytes() {
+        let size = state_size(&state(Platform::XoChip, 0, 0));
+        let mut buffer = RewindBuffer::new(REWIND_FRAMES, size);
+        buffer.push(state(Platform::XoChip, 0, 0));
+        assert_eq!(buffer.bytes(), size);
+        // the newest state alone fills the budget, so no frame fits beside it
+        buffer.push(state(Platform::XoChip, 1, 4));
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.bytes(), size);
+    }
+}
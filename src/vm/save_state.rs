@@ -0,0 +1,133 @@
+use crate::vm::{Platform, VmError, VmResult};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Bumped whenever the layout of [`SaveState`] changes
+pub const SAVE_STATE_VERSION: u8 = 2;
+
+/// Full machine state captured by [`Emulator::snapshot`](crate::vm::Emulator::snapshot).
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct SaveState {
+    pub platform: Platform,
+    pub pc: u16,
+    pub ram: Vec<u8>,
+    pub screen: Vec<u8>,
+    pub hires: bool,
+    pub planes: u8,
+    pub v_registers: [u8; 16],
+    pub i_register: u16,
+    pub stack: [u16; 16],
+    pub sp: u16,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub keys: [bool; 16],
+    pub rpl_flags: [u8; 16],
+    pub audio_pattern: Option<[u8; 16]>,
+    pub pitch: u8,
+    pub vblank: bool,
+    pub exited: bool,
+    pub rng: u32,
+}
+
+impl SaveState {
+    /// Version byte followed by the borsh encoded state
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![SAVE_STATE_VERSION];
+        // writing into a Vec can't fail
+        self.serialize(&mut bytes)
+            .expect("save state serialization failed");
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> VmResult<SaveState> {
+        match bytes.split_first() {
+            Some((&SAVE_STATE_VERSION, body)) => {
+                let state =
+                    SaveState::try_from_slice(body).map_err(|_| VmError::CorruptSaveState)?;
+                state.check()?;
+                Ok(state)
+            }
+            Some((&version, _)) => Err(VmError::UnsupportedSaveState { version }),
+            None => Err(VmError::CorruptSaveState),
+        }
+    }
+
+    /// Rejects states the machine could never be in, which would panic once restored: a
+    /// stack pointer past the stack, or planes and pixels outside the platform's bitplanes
+    pub fn check(&self) -> VmResult<()> {
+        let plane_mask = if self.platform.supports_xochip() {
+            0b11
+        } else {
+            0b01
+        };
+        if self.sp as usize > self.stack.len()
+            || self.planes & !plane_mask != 0
+            || self.screen.iter().any(|pixel| pixel & !plane_mask != 0)
+        {
+            return Err(VmError::CorruptSaveState);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{Emulator, Quirks};
+
+    fn corrupted(platform: Platform, corrupt: impl FnOnce(&mut SaveState)) -> Vec<u8> {
+        let mut state = Emulator::with_platform(platform, Quirks::default()).snapshot();
+        corrupt(&mut state);
+        state.to_bytes()
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut chip8 = Emulator::with_platform(Platform::XoChip, Quirks::XOCHIP);
+        chip8.load(&[0x60, 0x2A, 0x12, 0x02]).unwrap();
+        chip8.tick().unwrap();
+        let state = SaveState::from_bytes(&chip8.snapshot().to_bytes()).unwrap();
+
+        let mut restored = Emulator::with_platform(Platform::XoChip, Quirks::XOCHIP);
+        restored.restore(&state).unwrap();
+        assert_eq!(restored.snapshot(), chip8.snapshot());
+    }
+
+    #[test]
+    fn rejects_corrupted_states() {
+        let cases = [
+            corrupted(Platform::Chip8, |state| state.sp = 17),
+            corrupted(Platform::Chip8, |state| state.sp = u16::MAX),
+            corrupted(Platform::Chip8, |state| state.planes = 2),
+            corrupted(Platform::XoChip, |state| state.planes = 4),
+            corrupted(Platform::SuperChip, |state| state.screen[10] = 2),
+            corrupted(Platform::XoChip, |state| state.screen[10] = 0x80),
+        ];
+        for bytes in cases {
+            assert_eq!(
+                SaveState::from_bytes(&bytes),
+                Err(VmError::CorruptSaveState)
+            );
+        }
+
+        let bytes = corrupted(Platform::Chip8, |_| {});
+        assert_eq!(
+            SaveState::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(VmError::CorruptSaveState)
+        );
+        assert_eq!(
+            SaveState::from_bytes(&[SAVE_STATE_VERSION + 1]),
+            Err(VmError::UnsupportedSaveState {
+                version: SAVE_STATE_VERSION + 1
+            })
+        );
+    }
+
+    #[test]
+    fn restore_checks_states_built_in_code() {
+        let mut state = Emulator::new().snapshot();
+        state.sp = 17;
+        let mut chip8 = Emulator::new();
+        assert_eq!(chip8.restore(&state), Err(VmError::CorruptSaveState));
+        assert!(chip8.stack().is_empty());
+    }
+}
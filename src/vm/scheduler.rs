@@ -0,0 +1,35 @@
+/// Rate the timers count down at, and so how often a frame of instructions runs
+pub const FRAME_RATE: f64 = 60.0;
+const FRAME_MS: f64 = 1000.0 / FRAME_RATE;
+// more than this many frames behind (a background tab, a breakpoint) and the time is dropped
+const MAX_CATCH_UP_FRAMES: u32 = 6;
+
+/// Fixed-timestep clock turning wall time into whole 60Hz frames, whatever the display rate.
+#[derive(Debug, Default)]
+pub struct FrameScheduler {
+    last: Option<f64>,
+    accumulator: f64,
+}
+
+impl FrameScheduler {
+    /// Frames to run for the time elapsed up to `now`, in milliseconds
+    pub fn due_frames(&mut self, now: f64) -> u32 {
+        let elapsed = self.last.map_or(0.0, |last| (now - last).max(0.0));
+        self.last = Some(now);
+        self.accumulator += elapsed;
+
+        let due = (self.accumulator / FRAME_MS) as u32;
+        if due > MAX_CATCH_UP_FRAMES {
+            self.accumulator = 0.0;
+            return MAX_CATCH_UP_FRAMES;
+        }
+        self.accumulator -= due as f64 * FRAME_MS;
+        due
+    }
+
+    /// Forgets the elapsed time, so a pause isn't caught up on afterwards
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.accumulator = 0.0;
+    }
+}
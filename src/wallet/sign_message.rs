@@ -1,21 +1,9 @@
+#[cfg(feature = "hydrate")]
 use crate::error::{AppError, AppResult};
 
-pub struct Message {
-    pub valid_period: i64,
-    pub game_name: String,
-}
-
-impl Message {
-    pub fn to_string_message(&self) -> String {
-        format!(
-            "Replayer: game '{}' (valid until: {})",
-            self.game_name, self.valid_period
-        )
-    }
-}
-
+/// Has Phantom sign `message`, returning the base58 signature
 #[cfg(feature = "hydrate")]
-pub async fn sign_message(message: Message) -> AppResult<(String, String)> {
+pub async fn sign_message(message: &str) -> AppResult<String> {
     use crate::wallet::{is_phantom_installed, phantom_sign_message};
     use js_sys::Uint8Array;
     use wasm_bindgen::JsValue;
@@ -24,8 +12,7 @@ pub async fn sign_message(message: Message) -> AppResult<(String, String)> {
         return Err(AppError::custom("Phantom not installed"));
     }
 
-    let msg_string = message.to_string_message();
-    let msg_bytes = msg_string.as_bytes();
+    let msg_bytes = message.as_bytes();
     let uint8_array = Uint8Array::new_with_length(msg_bytes.len() as u32);
     uint8_array.copy_from(msg_bytes);
 
@@ -37,5 +24,44 @@ pub async fn sign_message(message: Message) -> AppResult<(String, String)> {
         .map_err(|e| AppError::custom(format!("{:?}", e)))?;
 
     let sig_bytes = Uint8Array::new(&signature).to_vec();
-    Ok((bs58::encode(sig_bytes).into_string(), msg_string))
+    Ok(bs58::encode(sig_bytes).into_string())
+}
+
+/// Signs in with the connected wallet for `purpose`, unless this browser already has a
+/// session for it. Returns the signed-in public key.
+#[cfg(feature = "hydrate")]
+pub async fn ensure_signed_in(purpose: crate::auth::Purpose) -> AppResult<String> {
+    use crate::server::{get_session, get_sign_in_message, sign_in};
+    use crate::wallet::get_public_key;
+
+    let public_key = get_public_key().await;
+    if get_session(purpose).await?.as_deref() == Some(public_key.as_str()) {
+        return Ok(public_key);
+    }
+
+    let domain = web_sys::window()
+        .and_then(|w| w.location().host().ok())
+        .ok_or(AppError::custom("Can't get page host"))?;
+    let message = get_sign_in_message(public_key.clone(), purpose).await?;
+    message.check_issued(&domain, &public_key, purpose)?;
+    let signature = sign_message(&message.to_text()).await?;
+    sign_in(message, signature).await
+}
+
+/// Has the wallet sign a [`GameGrant`] for `game_name`, when the backend checks wallet
+/// signatures rather than this server's. Prompts the wallet each time.
+#[cfg(feature = "hydrate")]
+pub async fn sign_game_grant(game_name: &str) -> AppResult<Option<crate::auth::GameGrant>> {
+    use crate::auth::{BackendAuth, GameGrant, GRANT_SECS};
+    use crate::server::get_backend_auth;
+
+    if get_backend_auth().await? == BackendAuth::Server {
+        return Ok(None);
+    }
+    let valid_period = (js_sys::Date::now() / 1000.0) as i64 + GRANT_SECS;
+    let signature = sign_message(&GameGrant::message(game_name, valid_period)).await?;
+    Ok(Some(GameGrant {
+        signature,
+        valid_period,
+    }))
 }